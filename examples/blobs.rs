@@ -217,6 +217,7 @@ mod shader {
                     UniformDesc::new("blobs_count", UniformType::Int1),
                     UniformDesc::new("blobs_positions", UniformType::Float2).array(32),
                 ],
+                ..Default::default()
             },
         }
     }
@@ -0,0 +1,127 @@
+use miniquad::*;
+
+#[repr(C)]
+struct Vec2 {
+    x: f32,
+    y: f32,
+}
+
+struct Stage {
+    pipeline: Pipeline,
+    bindings: Bindings,
+    offscreen_pass: RenderPass,
+    frame: u32,
+}
+
+impl Stage {
+    pub fn new(ctx: &mut Context) -> Stage {
+        #[rustfmt::skip]
+        let vertices: [Vec2; 3] = [
+            Vec2 { x: -0.5, y: -0.5 },
+            Vec2 { x:  0.5, y: -0.5 },
+            Vec2 { x:  0.0, y:  0.5 },
+        ];
+        let vertex_buffer = Buffer::immutable(ctx, BufferType::VertexBuffer, &vertices);
+
+        let indices: [u16; 3] = [0, 1, 2];
+        let index_buffer = Buffer::immutable(ctx, BufferType::IndexBuffer, &indices);
+
+        let bindings = Bindings {
+            vertex_buffers: vec![vertex_buffer],
+            index_buffer,
+            images: vec![],
+        };
+
+        let (w, h) = ctx.screen_size();
+        let color_img = Texture::new_render_texture(
+            ctx,
+            TextureParams {
+                width: w as _,
+                height: h as _,
+                format: TextureFormat::RGBA8,
+                ..Default::default()
+            },
+        );
+        let offscreen_pass = RenderPass::new(ctx, color_img, None);
+
+        let shader = Shader::new(ctx, shader::VERTEX, shader::FRAGMENT, shader::meta()).unwrap();
+
+        let pipeline = Pipeline::new(
+            ctx,
+            &[BufferLayout::default()],
+            &[VertexAttribute::new("pos", VertexFormat::Float2)],
+            shader,
+        );
+
+        Stage {
+            pipeline,
+            bindings,
+            offscreen_pass,
+            frame: 0,
+        }
+    }
+}
+
+impl EventHandler for Stage {
+    fn update(&mut self, _ctx: &mut Context) {}
+
+    fn draw(&mut self, ctx: &mut Context) {
+        ctx.begin_pass(self.offscreen_pass, PassAction::clear_color(0., 0., 1., 1.));
+        ctx.apply_pipeline(&self.pipeline);
+        ctx.apply_bindings(&self.bindings);
+        ctx.draw(0, 3, 1);
+        ctx.end_render_pass();
+
+        ctx.commit_frame();
+
+        self.frame += 1;
+        println!("frame {} rendered", self.frame);
+
+        let texture = self.offscreen_pass.texture(ctx);
+        let mut pixels = vec![0; texture.format.size(texture.width, texture.height) as usize];
+        texture.read_pixels(&mut pixels);
+
+        let center = ((texture.height / 2 * texture.width + texture.width / 2) * 4) as usize;
+        println!("center pixel: {:?}", &pixels[center..center + 4]);
+        println!("corner pixel: {:?}", &pixels[0..4]);
+    }
+}
+
+fn main() {
+    miniquad::start(
+        conf::Conf {
+            window_width: 64,
+            window_height: 64,
+            platform: conf::Platform {
+                linux_backend: conf::LinuxBackend::Headless,
+                headless_frame_count: Some(3),
+                ..Default::default()
+            },
+            ..Default::default()
+        },
+        |mut ctx| Box::new(Stage::new(&mut ctx)),
+    );
+}
+
+mod shader {
+    use miniquad::*;
+
+    pub const VERTEX: &str = r#"#version 100
+    attribute vec2 pos;
+
+    void main() {
+        gl_Position = vec4(pos, 0, 1);
+    }"#;
+
+    pub const FRAGMENT: &str = r#"#version 100
+    void main() {
+        gl_FragColor = vec4(1.0, 0.0, 0.0, 1.0);
+    }"#;
+
+    pub fn meta() -> ShaderMeta {
+        ShaderMeta {
+            images: vec![],
+            uniforms: UniformBlockLayout::default(),
+        }
+    }
+}
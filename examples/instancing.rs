@@ -179,6 +179,7 @@ mod shader {
             images: vec![],
             uniforms: UniformBlockLayout {
                 uniforms: vec![UniformDesc::new("mvp", UniformType::Mat4)],
+                ..Default::default()
             },
         }
     }
@@ -247,6 +247,7 @@ mod display_shader {
             images: vec!["tex".to_string()],
             uniforms: UniformBlockLayout {
                 uniforms: vec![UniformDesc::new("mvp", UniformType::Mat4)],
+                ..Default::default()
             },
         }
     }
@@ -287,6 +288,7 @@ mod offscreen_shader {
             images: vec![],
             uniforms: UniformBlockLayout {
                 uniforms: vec![UniformDesc::new("mvp", UniformType::Mat4)],
+                ..Default::default()
             },
         }
     }
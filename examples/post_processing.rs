@@ -34,7 +34,7 @@ impl Stage {
             },
         );
 
-        let offscreen_pass = RenderPass::new(ctx, color_img, depth_img);
+        let offscreen_pass = RenderPass::new_msaa(ctx, color_img, depth_img, 4);
 
         #[rustfmt::skip]
         let vertices: &[f32] = &[
@@ -190,7 +190,7 @@ impl EventHandler for Stage {
             },
         );
 
-        let offscreen_pass = RenderPass::new(ctx, color_img, depth_img);
+        let offscreen_pass = RenderPass::new_msaa(ctx, color_img, depth_img, 4);
 
         self.offscreen_pass.delete(ctx);
         self.offscreen_pass = offscreen_pass;
@@ -291,6 +291,7 @@ mod post_processing_shader {
             images: vec!["tex".to_string()],
             uniforms: UniformBlockLayout {
                 uniforms: vec![UniformDesc::new("resolution", UniformType::Float2)],
+                ..Default::default()
             },
         }
     }
@@ -332,6 +333,7 @@ mod offscreen_shader {
             images: vec![],
             uniforms: UniformBlockLayout {
                 uniforms: vec![UniformDesc::new("mvp", UniformType::Mat4)],
+                ..Default::default()
             },
         }
     }
@@ -121,6 +121,7 @@ mod shader {
             images: vec!["tex".to_string()],
             uniforms: UniformBlockLayout {
                 uniforms: vec![UniformDesc::new("offset", UniformType::Float2)],
+                ..Default::default()
             },
         }
     }
@@ -74,12 +74,23 @@ function acquireDisjointTimerQueryExtension(ctx) {
         ctx['endQuery'] = function (target) { return ext['endQueryEXT'](target); };
         ctx['deleteQuery'] = function (query) { ext['deleteQueryEXT'](query); };
         ctx['getQueryObject'] = function (query, pname) { return ext['getQueryObjectEXT'](query, pname); };
+        ctx['getQuery'] = function (target, pname) { return ext['getQueryEXT'](target, pname); };
+        ctx['queryCounter'] = function (query, target) { ext['queryCounterEXT'](query, target); };
+    }
+}
+
+function acquireDrawBuffersExtension(ctx) {
+    // Extension available in WebGL 1 on most desktop browsers. Core feature in WebGL 2.
+    var ext = ctx.getExtension('WEBGL_draw_buffers');
+    if (ext) {
+        ctx['drawBuffers'] = function (buffers) { ext['drawBuffersWEBGL'](buffers); };
     }
 }
 
 acquireVertexArrayObjectExtension(gl);
 acquireInstancedArraysExtension(gl);
 acquireDisjointTimerQueryExtension(gl);
+acquireDrawBuffersExtension(gl);
 
 // https://developer.mozilla.org/en-US/docs/Web/API/WEBGL_depth_texture
 if (gl.getExtension('WEBGL_depth_texture') == null) {
@@ -188,6 +199,7 @@ var GL = {
     timerQueries: [],
     contexts: {},
     programInfos: {},
+    stringCache: {},
 
     getNewId: function (table) {
         var ret = GL.counter++;
@@ -206,6 +218,21 @@ var GL = {
             }
         }
     },
+    writeActiveInfo: function (info, bufSize, length, size, type, name) {
+        if (!info) {
+            console.error("GL_INVALID_VALUE in glGetActiveUniform/glGetActiveAttrib");
+            return;
+        }
+        var written = 0;
+        if (bufSize > 0 && name) {
+            var heap = getArray(name, Uint8Array, bufSize);
+            written = stringToUTF8(info.name, heap, 0, bufSize - 1);
+            heap[written] = 0;
+        }
+        if (length) getArray(length, Int32Array, 1)[0] = written;
+        if (size) getArray(size, Int32Array, 1)[0] = info.size;
+        if (type) getArray(type, Uint32Array, 1)[0] = info.type;
+    },
     getSource: function (shader, count, string, length) {
         var source = '';
         for (var i = 0; i < count; ++i) {
@@ -361,7 +388,12 @@ function _webglGet(name_, p, type) {
                     result instanceof Int32Array ||
                     result instanceof Array) {
                     for (var i = 0; i < result.length; ++i) {
-                        assert(false, "unimplemented")
+                        switch (type) {
+                            case 'EM_FUNC_SIG_PARAM_I': getArray(p + i * 4, Int32Array, 1)[0] = result[i]; break;
+                            case 'EM_FUNC_SIG_PARAM_F': getArray(p + i * 4, Float32Array, 1)[0] = result[i]; break;
+                            case 'EM_FUNC_SIG_PARAM_B': getArray(p + i, Int8Array, 1)[0] = result[i] ? 1 : 0; break;
+                            default: throw 'internal glGet error, bad type: ' + type;
+                        }
                     }
                     return;
                 } else {
@@ -655,6 +687,14 @@ var importObject = {
             gl.texImage2D(target, level, internalFormat, width, height, border, format, type,
                 pixels ? getArray(pixels, Uint8Array, texture_size(internalFormat, width, height)) : null);
         },
+        glCompressedTexImage2D: function (target, level, internalFormat, width, height, border, imageSize, data) {
+            gl.compressedTexImage2D(target, level, internalFormat, width, height, border,
+                getArray(data, Uint8Array, imageSize));
+        },
+        glCompressedTexSubImage2D: function (target, level, xoffset, yoffset, width, height, format, imageSize, data) {
+            gl.compressedTexSubImage2D(target, level, xoffset, yoffset, width, height, format,
+                getArray(data, Uint8Array, imageSize));
+        },
         glTexSubImage2D: function (target, level, xoffset, yoffset, width, height, format, type, pixels) {
             gl.texSubImage2D(target, level, xoffset, yoffset, width, height, format, type,
                 pixels ? getArray(pixels, Uint8Array, texture_size(format, width, height)) : null);
@@ -666,6 +706,21 @@ var importObject = {
         glTexParameteri: function (target, pname, param) {
             gl.texParameteri(target, pname, param);
         },
+        glTexParameterf: function (target, pname, param) {
+            gl.texParameterf(target, pname, param);
+        },
+        glGetTexParameteriv: function (target, pname, p) {
+            getArray(p, Int32Array, 1)[0] = gl.getTexParameter(target, pname);
+        },
+        glGetTexParameterfv: function (target, pname, p) {
+            getArray(p, Float32Array, 1)[0] = gl.getTexParameter(target, pname);
+        },
+        glGetFloatv: function (name_, p) {
+            _webglGet(name_, p, 'EM_FUNC_SIG_PARAM_F');
+        },
+        glGenerateMipmap: function (target) {
+            gl.generateMipmap(target);
+        },
         glUniform1fv: function (location, count, value) {
             GL.validateGLObjectID(GL.uniforms, location, 'glUniform1fv', 'location');
             assert((value & 3) == 0, 'Pointer to float data passed to glUniform1fv must be aligned to four bytes!');
@@ -717,6 +772,12 @@ var importObject = {
         glBlendFunc: function (sfactor, dfactor) {
             gl.blendFunc(sfactor, dfactor);
         },
+        glBlendColor: function (red, green, blue, alpha) {
+            gl.blendColor(red, green, blue, alpha);
+        },
+        glLineWidth: function (width) {
+            gl.lineWidth(width);
+        },
         glBlendEquationSeparate: function (modeRGB, modeAlpha) {
             gl.blendEquationSeparate(modeRGB, modeAlpha);
         },
@@ -729,6 +790,28 @@ var importObject = {
         glGetIntegerv: function (name_, p) {
             _webglGet(name_, p, 'EM_FUNC_SIG_PARAM_I');
         },
+        glGetString: function (name_) {
+            // the strings never change, allocate each one once and keep it for good
+            if (GL.stringCache[name_] === undefined) {
+                var str;
+                if (name_ == 0x1F03 /* GL_EXTENSIONS */) {
+                    str = (gl.getSupportedExtensions() || []).join(' ');
+                } else {
+                    str = gl.getParameter(name_);
+                }
+                if (typeof (str) != "string") {
+                    GL.recordError(0x500); // GL_INVALID_ENUM
+                    return 0;
+                }
+                var len = (new TextEncoder().encode(str)).length;
+                var ptr = wasm_exports.allocate_vec_u8(len + 1);
+                var heap = new Uint8Array(wasm_memory.buffer, ptr, len + 1);
+                stringToUTF8(str, heap, 0, len);
+                heap[len] = 0;
+                GL.stringCache[name_] = ptr;
+            }
+            return GL.stringCache[name_];
+        },
         glUniform1f: function (location, v0) {
             GL.validateGLObjectID(GL.uniforms, location, 'glUniform1f', 'location');
             gl.uniform1f(GL.uniforms[location], v0);
@@ -868,11 +951,19 @@ var importObject = {
 
                 getArray(p, Int32Array, 1)[0] = log.length + 1;
             } else if (pname == 0x8B87 /* GL_ACTIVE_UNIFORM_MAX_LENGTH */) {
-                console.error("unsupported operation");
-                return;
+                var count = gl.getProgramParameter(GL.programs[program], 0x8B86 /* GL_ACTIVE_UNIFORMS */);
+                var max_length = 0;
+                for (var i = 0; i < count; i++) {
+                    max_length = Math.max(max_length, gl.getActiveUniform(GL.programs[program], i).name.length + 1);
+                }
+                getArray(p, Int32Array, 1)[0] = max_length;
             } else if (pname == 0x8B8A /* GL_ACTIVE_ATTRIBUTE_MAX_LENGTH */) {
-                console.error("unsupported operation");
-                return;
+                var count = gl.getProgramParameter(GL.programs[program], 0x8B89 /* GL_ACTIVE_ATTRIBUTES */);
+                var max_length = 0;
+                for (var i = 0; i < count; i++) {
+                    max_length = Math.max(max_length, gl.getActiveAttrib(GL.programs[program], i).name.length + 1);
+                }
+                getArray(p, Int32Array, 1)[0] = max_length;
             } else if (pname == 0x8A35 /* GL_ACTIVE_UNIFORM_BLOCK_MAX_NAME_LENGTH */) {
                 console.error("unsupported operation");
                 return;
@@ -940,6 +1031,14 @@ var importObject = {
 
             gl.shaderSource(GL.shaders[shader], source);
         },
+        glGetActiveUniform: function (program, index, bufSize, length, size, type, name) {
+            GL.validateGLObjectID(GL.programs, program, 'glGetActiveUniform', 'program');
+            GL.writeActiveInfo(gl.getActiveUniform(GL.programs[program], index), bufSize, length, size, type, name);
+        },
+        glGetActiveAttrib: function (program, index, bufSize, length, size, type, name) {
+            GL.validateGLObjectID(GL.programs, program, 'glGetActiveAttrib', 'program');
+            GL.writeActiveInfo(gl.getActiveAttrib(GL.programs[program], index), bufSize, length, size, type, name);
+        },
         glGetProgramInfoLog: function (program, maxLength, length, infoLog) {
             GL.validateGLObjectID(GL.programs, program, 'glGetProgramInfoLog', 'program');
             var log = gl.getProgramInfoLog(GL.programs[program]);
@@ -982,6 +1081,9 @@ var importObject = {
         glVertexAttribDivisor: function (index, divisor) {
             gl.vertexAttribDivisor(index, divisor);
         },
+        glDrawBuffers: function (n, bufs) {
+            gl.drawBuffers(Array.from(getArray(bufs, Uint32Array, n)));
+        },
         glDrawArraysInstanced: function (mode, first, count, primcount) {
             gl.drawArraysInstanced(mode, first, count, primcount);
         },
@@ -989,6 +1091,16 @@ var importObject = {
             gl.drawElementsInstanced(mode, count, type, indices, primcount);
         },
         glDeleteShader: function (shader) { gl.deleteShader(shader) },
+        glDeleteProgram: function (id) {
+            var program = GL.programs[id];
+            // From spec: "A value of 0 for program will be silently ignored."
+            if (!program) return;
+
+            gl.deleteProgram(program);
+            program.name = 0;
+            GL.programs[id] = null;
+            GL.programInfos[id] = null;
+        },
         glDeleteBuffers: function (n, buffers) {
             for (var i = 0; i < n; i++) {
                 var id = getArray(buffers + i * 4, Uint32Array, 1)[0];
@@ -1032,7 +1144,7 @@ var importObject = {
 		},
 		glDeleteQueries: function (n, ids) {
             for (var i = 0; i < n; i++) {
-                var id = getArray(textures + i * 4, Uint32Array, 1)[0];
+                var id = getArray(ids + i * 4, Uint32Array, 1)[0];
                 var query = GL.timerQueries[id];
                 if (!query) {
 					continue;
@@ -1049,11 +1161,23 @@ var importObject = {
 		glEndQuery: function (target) {
 			gl.endQuery(target);
 		},
+		glQueryCounter: function (id, target) {
+			GL.validateGLObjectID(GL.timerQueries, id, 'glQueryCounter', 'id');
+			gl.queryCounter(GL.timerQueries[id], target);
+		},
+		glGetQueryiv: function (target, pname, ptr) {
+			getArray(ptr, Int32Array, 1)[0] = gl.getQuery(target, pname);
+		},
 		glGetQueryObjectiv: function (id, pname, ptr) {
 			GL.validateGLObjectID(GL.timerQueries, id, 'glGetQueryObjectiv', 'id');
 			let result = gl.getQueryObject(GL.timerQueries[id], pname);
 			getArray(ptr, Uint32Array, 1)[0] = result;
 		},
+		glGetQueryObjectuiv: function (id, pname, ptr) {
+			GL.validateGLObjectID(GL.timerQueries, id, 'glGetQueryObjectuiv', 'id');
+			let result = gl.getQueryObject(GL.timerQueries[id], pname);
+			getArray(ptr, Uint32Array, 1)[0] = result;
+		},
 		glGetQueryObjectui64v: function (id, pname, ptr) {
 			GL.validateGLObjectID(GL.timerQueries, id, 'glGetQueryObjectui64v', 'id');
 			let result = gl.getQueryObject(GL.timerQueries[id], pname);
@@ -1195,6 +1319,35 @@ var importObject = {
                 }
             });
 
+            canvas.addEventListener("webglcontextlost", function (event) {
+                // without preventDefault the context would never be restored
+                event.preventDefault();
+                wasm_exports.context_lost();
+            });
+            canvas.addEventListener("webglcontextrestored", function (event) {
+                acquireVertexArrayObjectExtension(gl);
+                acquireInstancedArraysExtension(gl);
+                acquireDisjointTimerQueryExtension(gl);
+                acquireDrawBuffersExtension(gl);
+                gl.getExtension('WEBGL_depth_texture');
+
+                // objects of the lost context are gone, ids keep counting so
+                // stale handles on the rust side do not alias the new ones
+                GL.buffers = [];
+                GL.mappedBuffers = {};
+                GL.programs = [];
+                GL.framebuffers = [];
+                GL.renderbuffers = [];
+                GL.textures = [];
+                GL.uniforms = [];
+                GL.shaders = [];
+                GL.vaos = [];
+                GL.timerQueries = [];
+                GL.programInfos = {};
+
+                wasm_exports.context_restored();
+            });
+
             window.onresize = function () {
                 resize(canvas, wasm_exports.resize);
             };
@@ -1310,6 +1463,10 @@ var importObject = {
         sapp_set_cursor: function(ptr, len) {
             canvas.style.cursor = UTF8ToString(ptr, len);
         },
+        webgl_extension_supported: function (ptr, len) {
+            // getExtension also enables the extension
+            return gl.getExtension(UTF8ToString(ptr, len)) != null;
+        },
         sapp_is_fullscreen: function() {
             let fullscreenElement = document.fullscreenElement;
 
@@ -60,6 +60,11 @@ pub enum LinuxBackend {
     WaylandOnly,
     X11WithWaylandFallback,
     WaylandWithX11Fallback,
+    /// No window at all: GL context is created with EGL on an offscreen pbuffer,
+    /// using EGL_MESA_platform_surfaceless when available.
+    /// Screen size is fixed to `window_width` x `window_height`, there are no input events.
+    /// Useful for render tests on CI machines without a display server.
+    Headless,
 }
 
 /// Platform specific settings.
@@ -90,6 +95,18 @@ pub struct Platform {
     /// TODO: Make it works on web, on web it should make a transparent HTML5 canvas
     /// TODO: Document(and check) what does it actually mean on android. Transparent window?
     pub framebuffer_alpha: bool,
+
+    /// Number of frames to update and draw with `LinuxBackend::Headless` before
+    /// `miniquad::start` returns. If None - run until `order_quit` is called.
+    pub headless_frame_count: Option<u32>,
+
+    /// Ask GLX/EGL/WGL for a debug context and route the driver's KHR_debug
+    /// messages to the `log` module, or to `Context::set_debug_callback`.
+    /// Debug contexts may be noticeably slower, this is meant for development builds.
+    /// Ignored on wasm, macos and ios, see `Features::debug_output`.
+    ///
+    /// Default: false
+    pub gl_debug: bool,
 }
 
 impl Default for Platform {
@@ -99,6 +116,8 @@ impl Default for Platform {
             swap_interval: None,
             linux_backend: LinuxBackend::X11Only,
             framebuffer_alpha: false,
+            headless_frame_count: None,
+            gl_debug: false,
         }
     }
 }
@@ -139,6 +158,20 @@ pub struct Conf {
     /// - TODO: dock and titlebar icon on  MacOs
     pub icon: Option<Icon>,
 
+    /// Save linked shader programs to disk and load them back on the next run
+    /// instead of compiling. Entries are keyed by the shader sources and the GL driver,
+    /// binaries rejected by the driver are silently recompiled.
+    /// Ignored when `Features::program_binary` is false.
+    ///
+    /// Default: false
+    pub program_cache: bool,
+    /// Directory for `program_cache` entries, created when missing.
+    /// If None - "miniquad_program_cache" in `std::env::temp_dir()`,
+    /// which is not writable on android, so it is better to give an app specific path there.
+    ///
+    /// Default: None
+    pub program_cache_dir: Option<std::path::PathBuf>,
+
     /// Platform specific settings. Hints to OS for context creation, driver-specific
     /// settings etc.
     pub platform: Platform,
@@ -172,6 +205,20 @@ impl std::fmt::Debug for Icon {
     }
 }
 
+impl Conf {
+    /// Directory for the program cache, None if it is disabled.
+    pub(crate) fn program_cache_dir(&self) -> Option<std::path::PathBuf> {
+        if !self.program_cache {
+            return None;
+        }
+        Some(
+            self.program_cache_dir
+                .clone()
+                .unwrap_or_else(|| std::env::temp_dir().join("miniquad_program_cache")),
+        )
+    }
+}
+
 // reasonable defaults for PC and mobiles are slightly different
 #[cfg(not(target_os = "android"))]
 impl Default for Conf {
@@ -185,6 +232,8 @@ impl Default for Conf {
             sample_count: 1,
             window_resizable: true,
             icon: Some(Icon::miniquad_logo()),
+            program_cache: false,
+            program_cache_dir: None,
             platform: Default::default(),
         }
     }
@@ -202,6 +251,8 @@ impl Default for Conf {
             sample_count: 1,
             window_resizable: false,
             icon: Some(Icon::miniquad_logo()),
+            program_cache: false,
+            program_cache_dir: None,
             platform: Default::default(),
         }
     }
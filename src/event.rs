@@ -248,4 +248,16 @@ pub trait EventHandler {
     /// `ctx.dropped_file_path()`, and for wasm targets the file bytes
     /// can be requested with `ctx.dropped_file_bytes()`.
     fn files_dropped_event(&mut self, _ctx: &mut Context) {}
+
+    /// The GL context is gone, together with every `Buffer`, `Texture`, `Shader`,
+    /// `Pipeline` and `RenderPass` created with it.
+    /// Right now is only implemented on wasm, on "webglcontextlost", and on Android,
+    /// where a new context is created right away.
+    /// On wasm `draw` is not called until `context_restored_event`.
+    fn context_lost_event(&mut self, _ctx: &mut Context) {}
+
+    /// A new GL context replaced the lost one. Shaders, pipelines and buffers registered
+    /// with `ctx.enable_resource_registry()` are already recreated and their handles
+    /// are valid again, everything else should be created anew.
+    fn context_restored_event(&mut self, _ctx: &mut Context) {}
 }
@@ -1,12 +1,47 @@
-use std::{ffi::CString, mem};
-
+use std::{collections::HashMap, ffi::CString, mem};
+
+mod debug;
+mod preprocessor;
+mod profiler;
+mod program_cache;
+mod query;
+// only the android and wasm backends can lose their context and restore buffers
+#[cfg_attr(
+    not(any(target_os = "android", target_arch = "wasm32")),
+    allow(dead_code)
+)]
+mod registry;
+mod ring_buffer;
+mod sampler;
+mod slot_map;
 mod texture;
+#[cfg(not(any(target_arch = "wasm32", target_os = "android", target_os = "ios")))]
+mod watcher;
 
 use crate::{native::gl::*, Context};
 
+#[cfg(target_os = "ios")]
+use crate::native::query_stab::glGetQueryObjectui64v;
+
+use program_cache::ProgramCache;
+use registry::ResourceRegistry;
+use sampler::TextureSampling;
+use slot_map::{SlotId, SlotMap};
+
 use std::{error::Error, fmt::Display};
 
-pub use texture::{FilterMode, Texture, TextureAccess, TextureFormat, TextureParams, TextureWrap};
+pub use debug::{DebugMessage, DebugSeverity, DebugSource, DebugType};
+pub use preprocessor::{GlslVersion, PreprocessedSource, Preprocessor};
+pub use profiler::{GpuProfiler, ProfilerFrame, ProfilerScope};
+pub use query::{Query, QueryType};
+pub use ring_buffer::RingBuffer;
+pub use sampler::{Sampler, SamplerParams};
+pub use texture::{
+    CubeFace, FilterMode, MipmapFilterMode, Texture, TextureAccess, TextureFormat, TextureKind,
+    TextureParams, TextureRect, TextureWrap,
+};
+#[cfg(not(any(target_arch = "wasm32", target_os = "android", target_os = "ios")))]
+pub use watcher::ShaderWatcher;
 
 fn get_uniform_location(program: GLuint, name: &str) -> Option<i32> {
     let cname = CString::new(name).unwrap_or_else(|e| panic!("{}", e));
@@ -19,7 +54,7 @@ fn get_uniform_location(program: GLuint, name: &str) -> Option<i32> {
     Some(location)
 }
 
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, PartialEq)]
 pub enum UniformType {
     /// One 32-bit wide float (equivalent to `f32`)
     Float1,
@@ -56,18 +91,114 @@ impl UniformType {
             UniformType::Mat4 => 64,
         }
     }
+
+    /// Base alignment inside a std140 uniform block
+    fn std140_align(&self) -> usize {
+        match self {
+            UniformType::Float1 | UniformType::Int1 => 4,
+            UniformType::Float2 | UniformType::Int2 => 8,
+            _ => 16,
+        }
+    }
+
+    /// UniformType for a type reported by glGetActiveUniform, bools are set as ints.
+    fn from_gl_type(gl_type: GLenum) -> Option<UniformType> {
+        match gl_type {
+            GL_FLOAT => Some(UniformType::Float1),
+            GL_FLOAT_VEC2 => Some(UniformType::Float2),
+            GL_FLOAT_VEC3 => Some(UniformType::Float3),
+            GL_FLOAT_VEC4 => Some(UniformType::Float4),
+            GL_INT | GL_BOOL => Some(UniformType::Int1),
+            GL_INT_VEC2 | GL_BOOL_VEC2 => Some(UniformType::Int2),
+            GL_INT_VEC3 | GL_BOOL_VEC3 => Some(UniformType::Int3),
+            GL_INT_VEC4 | GL_BOOL_VEC4 => Some(UniformType::Int4),
+            GL_FLOAT_MAT4 => Some(UniformType::Mat4),
+            _ => None,
+        }
+    }
 }
 
-#[derive(Clone)]
+fn is_sampler_type(gl_type: GLenum) -> bool {
+    matches!(
+        gl_type,
+        GL_SAMPLER_2D
+            | GL_SAMPLER_3D
+            | GL_SAMPLER_CUBE
+            | GL_SAMPLER_2D_SHADOW
+            | GL_SAMPLER_2D_ARRAY
+            | GL_SAMPLER_2D_ARRAY_SHADOW
+            | GL_SAMPLER_CUBE_SHADOW
+    )
+}
+
+#[derive(Clone, Debug)]
 pub struct UniformDesc {
-    name: String,
-    uniform_type: UniformType,
-    array_count: usize,
+    pub name: String,
+    pub uniform_type: UniformType,
+    pub array_count: usize,
 }
 
-#[derive(Clone)]
+#[derive(Clone, Debug, Default)]
 pub struct UniformBlockLayout {
     pub uniforms: Vec<UniformDesc>,
+    /// Name of the `layout(std140) uniform` block the uniforms are declared in,
+    /// see `UniformBlockLayout::std140`. None for plain uniforms.
+    pub std140_block: Option<String>,
+}
+
+impl UniformBlockLayout {
+    /// Plain uniforms, set one by one from a tightly packed struct.
+    pub fn new(uniforms: Vec<UniformDesc>) -> UniformBlockLayout {
+        UniformBlockLayout {
+            uniforms,
+            std140_block: None,
+        }
+    }
+
+    /// Uniforms of the `layout(std140) uniform` block named `block`.
+    /// The uniforms struct is expected to follow std140 rules and is uploaded
+    /// into a uniform buffer with one call.
+    /// On gles2 there are no uniform blocks: the shader should declare plain
+    /// uniforms, they will be read from the struct with std140 offsets.
+    pub fn std140(block: &str, uniforms: Vec<UniformDesc>) -> UniformBlockLayout {
+        UniformBlockLayout {
+            uniforms,
+            std140_block: Some(block.to_string()),
+        }
+    }
+
+    /// (offset, array stride) of each uniform in the Rust-side struct and the end of the last one.
+    fn offsets(&self) -> (Vec<(usize, usize)>, usize) {
+        let mut offset = 0usize;
+        let offsets = self
+            .uniforms
+            .iter()
+            .map(|uniform| {
+                let size = uniform.uniform_type.size();
+                let (align, stride) = match &self.std140_block {
+                    None => (1, size),
+                    Some(_) if uniform.array_count > 1 => (16, size.div_ceil(16) * 16),
+                    Some(_) => (uniform.uniform_type.std140_align(), size),
+                };
+                let res = (offset.div_ceil(align) * align, stride);
+                // std140 arrays take whole elements, padding of the last one included
+                offset = if uniform.array_count > 1 {
+                    res.0 + stride * uniform.array_count
+                } else {
+                    res.0 + size
+                };
+                res
+            })
+            .collect();
+
+        (offsets, offset)
+    }
+
+    /// Size of the uniforms struct matching this layout.
+    /// For std140 blocks the struct may also be padded up to 16 bytes.
+    pub fn size(&self) -> usize {
+        self.offsets().1
+    }
 }
 
 impl UniformDesc {
@@ -87,7 +218,7 @@ impl UniformDesc {
     }
 }
 
-#[derive(Clone)]
+#[derive(Clone, Debug)]
 pub struct ShaderMeta {
     pub uniforms: UniformBlockLayout,
     pub images: Vec<String>,
@@ -129,74 +260,161 @@ pub enum VertexFormat {
     Int4,
     /// Four by four matrix of 32-bit floats
     Mat4,
+    /// One unsigned 8-bit integer mapped to 0..1 (equivalent to `u8`)
+    Byte1Norm,
+    /// Two unsigned 8-bit integers mapped to 0..1 (equivalent to `[u8; 2]`)
+    Byte2Norm,
+    /// Three unsigned 8-bit integers mapped to 0..1 (equivalent to `[u8; 3]`)
+    Byte3Norm,
+    /// Four unsigned 8-bit integers mapped to 0..1 (equivalent to `[u8; 4]`)
+    Byte4Norm,
+    /// One signed 8-bit integer (equivalent to `i8`)
+    SByte1,
+    /// Two signed 8-bit integers (equivalent to `[i8; 2]`)
+    SByte2,
+    /// Three signed 8-bit integers (equivalent to `[i8; 3]`)
+    SByte3,
+    /// Four signed 8-bit integers (equivalent to `[i8; 4]`)
+    SByte4,
+    /// One signed 8-bit integer mapped to -1..1 (equivalent to `i8`)
+    SByte1Norm,
+    /// Two signed 8-bit integers mapped to -1..1 (equivalent to `[i8; 2]`)
+    SByte2Norm,
+    /// Three signed 8-bit integers mapped to -1..1 (equivalent to `[i8; 3]`)
+    SByte3Norm,
+    /// Four signed 8-bit integers mapped to -1..1 (equivalent to `[i8; 4]`)
+    SByte4Norm,
+    /// One unsigned 16-bit integer mapped to 0..1 (equivalent to `u16`)
+    Short1Norm,
+    /// Two unsigned 16-bit integers mapped to 0..1 (equivalent to `[u16; 2]`)
+    Short2Norm,
+    /// Three unsigned 16-bit integers mapped to 0..1 (equivalent to `[u16; 3]`)
+    Short3Norm,
+    /// Four unsigned 16-bit integers mapped to 0..1 (equivalent to `[u16; 4]`)
+    Short4Norm,
+    /// One signed 16-bit integer (equivalent to `i16`)
+    SShort1,
+    /// Two signed 16-bit integers (equivalent to `[i16; 2]`)
+    SShort2,
+    /// Three signed 16-bit integers (equivalent to `[i16; 3]`)
+    SShort3,
+    /// Four signed 16-bit integers (equivalent to `[i16; 4]`)
+    SShort4,
+    /// One signed 16-bit integer mapped to -1..1 (equivalent to `i16`)
+    SShort1Norm,
+    /// Two signed 16-bit integers mapped to -1..1 (equivalent to `[i16; 2]`)
+    SShort2Norm,
+    /// Three signed 16-bit integers mapped to -1..1 (equivalent to `[i16; 3]`)
+    SShort3Norm,
+    /// Four signed 16-bit integers mapped to -1..1 (equivalent to `[i16; 4]`)
+    SShort4Norm,
+    /// One signed 32-bit integer (equivalent to `i32`)
+    SInt1,
+    /// Two signed 32-bit integers (equivalent to `[i32; 2]`)
+    SInt2,
+    /// Three signed 32-bit integers (equivalent to `[i32; 3]`)
+    SInt3,
+    /// Four signed 32-bit integers (equivalent to `[i32; 4]`)
+    SInt4,
+    /// Two 16-bit wide floats (equivalent to `[u16; 2]` of IEEE half floats).
+    /// Not available on gles2 and webgl1.
+    Half2,
+    /// Four 16-bit wide floats (equivalent to `[u16; 4]` of IEEE half floats).
+    /// Not available on gles2 and webgl1.
+    Half4,
+    /// Signed 10-bit x, y, z and 2-bit w packed into one `u32`, x in the lowest bits,
+    /// mapped to -1..1. Not available on gles2 and webgl1.
+    #[allow(non_camel_case_types)]
+    Int2_10_10_10_Rev,
 }
 
 impl VertexFormat {
+    /// Number of components.
     pub fn size(&self) -> i32 {
+        use VertexFormat::*;
+
         match self {
-            VertexFormat::Float1 => 1,
-            VertexFormat::Float2 => 2,
-            VertexFormat::Float3 => 3,
-            VertexFormat::Float4 => 4,
-            VertexFormat::Byte1 => 1,
-            VertexFormat::Byte2 => 2,
-            VertexFormat::Byte3 => 3,
-            VertexFormat::Byte4 => 4,
-            VertexFormat::Short1 => 1,
-            VertexFormat::Short2 => 2,
-            VertexFormat::Short3 => 3,
-            VertexFormat::Short4 => 4,
-            VertexFormat::Int1 => 1,
-            VertexFormat::Int2 => 2,
-            VertexFormat::Int3 => 3,
-            VertexFormat::Int4 => 4,
-            VertexFormat::Mat4 => 16,
+            Float1 | Byte1 | Short1 | Int1 | Byte1Norm | SByte1 | SByte1Norm | Short1Norm
+            | SShort1 | SShort1Norm | SInt1 => 1,
+            Float2 | Byte2 | Short2 | Int2 | Byte2Norm | SByte2 | SByte2Norm | Short2Norm
+            | SShort2 | SShort2Norm | SInt2 | Half2 => 2,
+            Float3 | Byte3 | Short3 | Int3 | Byte3Norm | SByte3 | SByte3Norm | Short3Norm
+            | SShort3 | SShort3Norm | SInt3 => 3,
+            Float4 | Byte4 | Short4 | Int4 | Byte4Norm | SByte4 | SByte4Norm | Short4Norm
+            | SShort4 | SShort4Norm | SInt4 | Half4 | Int2_10_10_10_Rev => 4,
+            Mat4 => 16,
         }
     }
 
     pub fn byte_len(&self) -> i32 {
-        match self {
-            VertexFormat::Float1 => 1 * 4,
-            VertexFormat::Float2 => 2 * 4,
-            VertexFormat::Float3 => 3 * 4,
-            VertexFormat::Float4 => 4 * 4,
-            VertexFormat::Byte1 => 1,
-            VertexFormat::Byte2 => 2,
-            VertexFormat::Byte3 => 3,
-            VertexFormat::Byte4 => 4,
-            VertexFormat::Short1 => 1 * 2,
-            VertexFormat::Short2 => 2 * 2,
-            VertexFormat::Short3 => 3 * 2,
-            VertexFormat::Short4 => 4 * 2,
-            VertexFormat::Int1 => 1 * 4,
-            VertexFormat::Int2 => 2 * 4,
-            VertexFormat::Int3 => 3 * 4,
-            VertexFormat::Int4 => 4 * 4,
-            VertexFormat::Mat4 => 16 * 4,
+        match self.type_() {
+            GL_BYTE | GL_UNSIGNED_BYTE => self.size(),
+            GL_SHORT | GL_UNSIGNED_SHORT | GL_HALF_FLOAT => self.size() * 2,
+            GL_INT_2_10_10_10_REV => 4,
+            _ => self.size() * 4,
         }
     }
 
     fn type_(&self) -> GLuint {
+        use VertexFormat::*;
+
         match self {
-            VertexFormat::Float1 => GL_FLOAT,
-            VertexFormat::Float2 => GL_FLOAT,
-            VertexFormat::Float3 => GL_FLOAT,
-            VertexFormat::Float4 => GL_FLOAT,
-            VertexFormat::Byte1 => GL_UNSIGNED_BYTE,
-            VertexFormat::Byte2 => GL_UNSIGNED_BYTE,
-            VertexFormat::Byte3 => GL_UNSIGNED_BYTE,
-            VertexFormat::Byte4 => GL_UNSIGNED_BYTE,
-            VertexFormat::Short1 => GL_UNSIGNED_SHORT,
-            VertexFormat::Short2 => GL_UNSIGNED_SHORT,
-            VertexFormat::Short3 => GL_UNSIGNED_SHORT,
-            VertexFormat::Short4 => GL_UNSIGNED_SHORT,
-            VertexFormat::Int1 => GL_UNSIGNED_INT,
-            VertexFormat::Int2 => GL_UNSIGNED_INT,
-            VertexFormat::Int3 => GL_UNSIGNED_INT,
-            VertexFormat::Int4 => GL_UNSIGNED_INT,
-            VertexFormat::Mat4 => GL_FLOAT,
+            Float1 | Float2 | Float3 | Float4 | Mat4 => GL_FLOAT,
+            Byte1 | Byte2 | Byte3 | Byte4 => GL_UNSIGNED_BYTE,
+            Byte1Norm | Byte2Norm | Byte3Norm | Byte4Norm => GL_UNSIGNED_BYTE,
+            SByte1 | SByte2 | SByte3 | SByte4 => GL_BYTE,
+            SByte1Norm | SByte2Norm | SByte3Norm | SByte4Norm => GL_BYTE,
+            Short1 | Short2 | Short3 | Short4 => GL_UNSIGNED_SHORT,
+            Short1Norm | Short2Norm | Short3Norm | Short4Norm => GL_UNSIGNED_SHORT,
+            SShort1 | SShort2 | SShort3 | SShort4 => GL_SHORT,
+            SShort1Norm | SShort2Norm | SShort3Norm | SShort4Norm => GL_SHORT,
+            Int1 | Int2 | Int3 | Int4 => GL_UNSIGNED_INT,
+            SInt1 | SInt2 | SInt3 | SInt4 => GL_INT,
+            Half2 | Half4 => GL_HALF_FLOAT,
+            Int2_10_10_10_Rev => GL_INT_2_10_10_10_REV,
         }
     }
+
+    /// Integers are mapped to 0..1 (unsigned) or -1..1 (signed) instead of converted to floats.
+    pub fn normalized(&self) -> bool {
+        use VertexFormat::*;
+
+        matches!(
+            self,
+            Byte1Norm
+                | Byte2Norm
+                | Byte3Norm
+                | Byte4Norm
+                | SByte1Norm
+                | SByte2Norm
+                | SByte3Norm
+                | SByte4Norm
+                | Short1Norm
+                | Short2Norm
+                | Short3Norm
+                | Short4Norm
+                | SShort1Norm
+                | SShort2Norm
+                | SShort3Norm
+                | SShort4Norm
+                | Int2_10_10_10_Rev
+        )
+    }
+
+    /// Can feed `int`/`uint` vector attributes of the shader, which get the values as is.
+    /// Fed to float attributes these formats are converted to floats.
+    pub fn is_integer(&self) -> bool {
+        !self.normalized()
+            && matches!(
+                self.type_(),
+                GL_BYTE
+                    | GL_UNSIGNED_BYTE
+                    | GL_SHORT
+                    | GL_UNSIGNED_SHORT
+                    | GL_INT
+                    | GL_UNSIGNED_INT
+            )
+    }
 }
 
 #[derive(Clone, Copy, PartialEq, Debug)]
@@ -233,6 +451,9 @@ pub struct VertexAttribute {
     pub name: &'static str,
     pub format: VertexFormat,
     pub buffer_index: usize,
+    /// Byte offset in the buffer's vertex. None is right after the previous attribute
+    /// of the same buffer.
+    pub offset: Option<i32>,
 }
 
 impl VertexAttribute {
@@ -249,6 +470,20 @@ impl VertexAttribute {
             name,
             format,
             buffer_index,
+            offset: None,
+        }
+    }
+
+    /// Explicit offset, for interleaved layouts with padding or attributes out of order.
+    /// ```ignore
+    /// VertexAttribute::new("pos", VertexFormat::Float3),
+    /// // 4 bytes of padding after the position
+    /// VertexAttribute::new("uv", VertexFormat::Float2).offset(16),
+    /// ```
+    pub const fn offset(self, offset: i32) -> VertexAttribute {
+        VertexAttribute {
+            offset: Some(offset),
+            ..self
         }
     }
 }
@@ -274,6 +509,23 @@ pub enum ShaderError {
     LinkError(String),
     /// Shader strings should never contains \00 in the middle
     FFINulError(std::ffi::NulError),
+    /// `ShaderMeta` entries or `VertexAttribute`s not found in the linked program
+    /// or having a different type, one line per mismatch.
+    /// Checked only with `GraphicsContext::set_meta_validation`, except for attributes
+    /// of missing buffer layouts and `int`/`uint` attributes with a float or normalized format.
+    MetaMismatch(String),
+    /// `Pipeline` buffer stride over `Features::max_vertex_attrib_stride`.
+    VertexStrideTooLarge {
+        buffer_index: usize,
+        stride: i32,
+        max_stride: i32,
+    },
+    /// `Preprocessor` failed on `#include` or another directive.
+    PreprocessError {
+        file: String,
+        line: usize,
+        message: String,
+    },
 }
 
 impl From<std::ffi::NulError> for ShaderError {
@@ -294,8 +546,8 @@ impl Error for ShaderError {
     }
 }
 
-#[derive(Clone, Debug, Copy)]
-pub struct Shader(usize);
+#[derive(Clone, Debug, Copy, PartialEq)]
+pub struct Shader(SlotId);
 
 impl Shader {
     pub fn new(
@@ -304,10 +556,419 @@ impl Shader {
         fragment_shader: &str,
         meta: ShaderMeta,
     ) -> Result<Shader, ShaderError> {
-        let shader = load_shader_internal(vertex_shader, fragment_shader, meta)?;
-        ctx.shaders.push(shader);
-        Ok(Shader(ctx.shaders.len() - 1))
+        let mut shader = load_shader_internal(
+            vertex_shader,
+            fragment_shader,
+            Some(meta),
+            ctx.features.uniform_buffers,
+            ctx.program_cache.as_ref(),
+            ctx.meta_validation,
+        )?;
+        shader.sources = registered_sources(ctx, vertex_shader, fragment_shader);
+        Ok(Shader(ctx.shaders.insert(shader)))
+    }
+
+    /// Create a shader from sources passed through `preprocessor`.
+    /// Compilation errors refer to the original files and lines.
+    pub fn new_preprocessed(
+        ctx: &mut Context,
+        preprocessor: &Preprocessor,
+        vertex_shader: &str,
+        fragment_shader: &str,
+        meta: ShaderMeta,
+    ) -> Result<Shader, ShaderError> {
+        preprocessor.with_sources(
+            ("vertex", vertex_shader),
+            ("fragment", fragment_shader),
+            |vertex, fragment| Shader::new(ctx, vertex, fragment, meta),
+        )
+    }
+
+    /// Create a shader with `ShaderMeta` built from the program's active uniforms.
+    ///
+    /// Images and uniforms are sorted by name, so the uniforms struct should
+    /// follow the same order. Uniforms inside uniform blocks are not reflected.
+    pub fn new_reflected(
+        ctx: &mut Context,
+        vertex_shader: &str,
+        fragment_shader: &str,
+    ) -> Result<Shader, ShaderError> {
+        let mut shader = load_shader_internal(
+            vertex_shader,
+            fragment_shader,
+            None,
+            ctx.features.uniform_buffers,
+            ctx.program_cache.as_ref(),
+            ctx.meta_validation,
+        )?;
+        shader.sources = registered_sources(ctx, vertex_shader, fragment_shader);
+        Ok(Shader(ctx.shaders.insert(shader)))
+    }
+
+    /// Replace the program with the one built from new sources, keeping the `Shader` handle
+    /// and all the pipelines using it valid. ShaderMeta stays the same, or is reflected again
+    /// for shaders created with `new_reflected`.
+    ///
+    /// On error the old program is kept. That includes `ShaderError::MetaMismatch`
+    /// when the new program puts a pipeline attribute at a location the pipeline
+    /// has no room for.
+    pub fn reload(
+        &self,
+        ctx: &mut Context,
+        vertex_shader: &str,
+        fragment_shader: &str,
+    ) -> Result<(), ShaderError> {
+        let old = &ctx.shaders[self.0];
+        let meta = if old.reflected {
+            None
+        } else {
+            Some(old.meta.clone())
+        };
+        let res = load_shader_internal(
+            vertex_shader,
+            fragment_shader,
+            meta,
+            ctx.features.uniform_buffers,
+            ctx.program_cache.as_ref(),
+            ctx.meta_validation,
+        );
+
+        // every pipeline has to fit the new attribute locations before anything is replaced
+        let res = res.and_then(|shader| {
+            let layouts: Result<Vec<_>, String> = ctx
+                .pipelines
+                .iter()
+                .filter(|pipeline| pipeline.shader == *self)
+                .map(|pipeline| {
+                    vertex_layout(
+                        shader.program,
+                        &pipeline.buffer_layout,
+                        &pipeline.attributes,
+                    )
+                })
+                .collect();
+            match layouts {
+                Ok(layouts) => {
+                    let pipelines = ctx
+                        .pipelines
+                        .iter_mut()
+                        .filter(|pipeline| pipeline.shader == *self);
+                    for (pipeline, layout) in pipelines.zip(layouts) {
+                        pipeline.layout = layout;
+                    }
+                    unsafe { glDeleteProgram(ctx.shaders[self.0].program) };
+                    Ok(shader)
+                }
+                Err(err) => {
+                    unsafe { glDeleteProgram(shader.program) };
+                    Err(ShaderError::MetaMismatch(err))
+                }
+            }
+        });
+        let res = res.map(|mut shader| {
+            shader.sources = registered_sources(ctx, vertex_shader, fragment_shader);
+            shader.label = ctx.shaders[self.0].label.take();
+            if let Some(label) = &shader.label {
+                ctx.object_label(GL_PROGRAM, shader.program, label);
+            }
+            ctx.shaders[self.0] = shader;
+        });
+
+        // linking made the new program current, restore the applied pipeline's one
+        if let Some(pipeline) = ctx.cache.cur_pipeline {
+            let shader = ctx.pipelines[pipeline.0].shader;
+            unsafe {
+                glUseProgram(ctx.shaders[shader.0].program);
+            }
+        }
+
+        res
+    }
+
+    /// `reload` with sources passed through `preprocessor`.
+    pub fn reload_preprocessed(
+        &self,
+        ctx: &mut Context,
+        preprocessor: &Preprocessor,
+        vertex_shader: &str,
+        fragment_shader: &str,
+    ) -> Result<(), ShaderError> {
+        preprocessor.with_sources(
+            ("vertex", vertex_shader),
+            ("fragment", fragment_shader),
+            |vertex, fragment| self.reload(ctx, vertex, fragment),
+        )
+    }
+
+    /// Name the program in driver messages and frame debuggers, kept across `reload`.
+    /// Does nothing without `features.debug_output`.
+    pub fn label(&self, ctx: &mut Context, label: &str) {
+        let program = ctx.shaders[self.0].program;
+        ctx.object_label(GL_PROGRAM, program, label);
+        ctx.shaders[self.0].label = Some(label.to_string());
+    }
+
+    /// ShaderMeta the shader was created with or reflected from the program.
+    pub fn meta(&self, ctx: &Context) -> ShaderMeta {
+        ctx.shaders[self.0].meta.clone()
+    }
+
+    /// Delete the GL program and free the handle.
+    /// Using the shader, or applying a pipeline created with it, panics afterwards.
+    pub fn delete(&self, ctx: &mut Context) {
+        let shader = ctx
+            .shaders
+            .remove(self.0)
+            .expect("Shader used after delete");
+
+        // the applied pipeline can not be used any more
+        if let Some(pipeline) = ctx.cache.cur_pipeline {
+            let pipeline_shader = ctx
+                .pipelines
+                .get(pipeline.0)
+                .map(|pipeline| pipeline.shader);
+            if pipeline_shader.is_none() || pipeline_shader == Some(*self) {
+                ctx.cache.cur_pipeline = None;
+            }
+        }
+
+        unsafe {
+            let mut current_program = 0;
+            glGetIntegerv(GL_CURRENT_PROGRAM, &mut current_program as *mut _);
+            if current_program as GLuint == shader.program {
+                glUseProgram(0);
+            }
+            glDeleteProgram(shader.program);
+        }
+    }
+}
+
+/// `ShaderInternal::sources`, if the context keeps a resource registry.
+fn registered_sources(
+    ctx: &Context,
+    vertex_shader: &str,
+    fragment_shader: &str,
+) -> Option<(String, String)> {
+    ctx.resource_registry
+        .as_ref()
+        .map(|_| (vertex_shader.to_string(), fragment_shader.to_string()))
+}
+
+/// Sampler emulation without `features.sampler_objects`: the sampler state is written
+/// into `texture`, bound to `target`, and its own state is kept in `emulated_samplers`
+/// to be written back once the texture is bound without a sampler.
+unsafe fn emulate_sampler(
+    emulated_samplers: &mut HashMap<GLuint, (TextureSampling, TextureSampling)>,
+    max_anisotropy: f32,
+    target: GLenum,
+    texture: GLuint,
+    sampler: Option<Sampler>,
+) {
+    match sampler {
+        Some(sampler) => {
+            let sampling = TextureSampling::from_params(&sampler.params, max_anisotropy);
+            let (_, applied) = emulated_samplers.entry(texture).or_insert_with(|| {
+                let own = TextureSampling::read(target, max_anisotropy);
+                (own, own)
+            });
+            if *applied != sampling {
+                sampling.write(target, max_anisotropy);
+                *applied = sampling;
+            }
+        }
+        None => {
+            if let Some((own, _)) = emulated_samplers.remove(&texture) {
+                own.write(target, max_anisotropy);
+            }
+        }
+    }
+}
+
+/// Uniform or attribute as reported by glGetActiveUniform/glGetActiveAttrib.
+struct ActiveVariable {
+    name: String,
+    gl_type: GLenum,
+    size: i32,
+}
+
+unsafe fn active_variables(program: GLuint, uniforms: bool) -> Vec<ActiveVariable> {
+    let (count_param, length_param) = if uniforms {
+        (GL_ACTIVE_UNIFORMS, GL_ACTIVE_UNIFORM_MAX_LENGTH)
+    } else {
+        (GL_ACTIVE_ATTRIBUTES, GL_ACTIVE_ATTRIBUTE_MAX_LENGTH)
+    };
+    let get_active = if uniforms {
+        glGetActiveUniform
+    } else {
+        glGetActiveAttrib
+    };
+
+    let mut count = 0;
+    glGetProgramiv(program, count_param, &mut count as *mut _);
+    let mut max_length = 0;
+    glGetProgramiv(program, length_param, &mut max_length as *mut _);
+
+    let mut name = vec![0u8; max_length.max(1) as usize];
+    (0..count as GLuint)
+        .map(|index| {
+            let mut length = 0;
+            let mut size = 0;
+            let mut gl_type = 0;
+            get_active(
+                program,
+                index,
+                name.len() as _,
+                &mut length as *mut _,
+                &mut size as *mut _,
+                &mut gl_type as *mut _,
+                name.as_mut_ptr() as *mut _,
+            );
+            let name = String::from_utf8_lossy(&name[..length as usize]);
+            ActiveVariable {
+                // arrays are reported by their first element
+                name: name.trim_end_matches("[0]").to_string(),
+                gl_type,
+                size,
+            }
+        })
+        .collect()
+}
+
+/// Uniform as it's named in `ShaderMeta`: block members may be prefixed by the block instance name.
+fn find_uniform<'a>(
+    active_uniforms: &'a [ActiveVariable],
+    name: &str,
+) -> Option<&'a ActiveVariable> {
+    active_uniforms
+        .iter()
+        .find(|uniform| uniform.name == name || uniform.name.rsplit('.').next() == Some(name))
+}
+
+fn validate_meta(meta: &ShaderMeta, active_uniforms: &[ActiveVariable]) -> Vec<String> {
+    let mut errors = vec![];
+
+    for image in &meta.images {
+        match find_uniform(active_uniforms, image) {
+            None => errors.push(format!("image \"{}\" not found in the program", image)),
+            Some(uniform) if !is_sampler_type(uniform.gl_type) => errors.push(format!(
+                "image \"{}\" is not a sampler in the program",
+                image
+            )),
+            _ => {}
+        }
+    }
+
+    for desc in &meta.uniforms.uniforms {
+        match find_uniform(active_uniforms, &desc.name) {
+            None => errors.push(format!(
+                "uniform \"{}\" not found in the program",
+                desc.name
+            )),
+            Some(uniform) => {
+                if UniformType::from_gl_type(uniform.gl_type) != Some(desc.uniform_type) {
+                    errors.push(format!(
+                        "uniform \"{}\" is {:?} in ShaderMeta, but GL type {:#x} in the program",
+                        desc.name, desc.uniform_type, uniform.gl_type
+                    ));
+                }
+                // unused trailing elements may be dropped by the driver, so only too short arrays are reported
+                if uniform.size as usize > desc.array_count {
+                    errors.push(format!(
+                        "uniform \"{}\" has {} elements in ShaderMeta, but {} in the program",
+                        desc.name, desc.array_count, uniform.size
+                    ));
+                }
+            }
+        }
+    }
+
+    errors
+}
+
+/// `int`, `uint` and their vectors, set with glVertexAttribIPointer.
+fn is_integer_attribute(gl_type: GLenum) -> bool {
+    matches!(
+        gl_type,
+        GL_INT
+            | GL_INT_VEC2
+            | GL_INT_VEC3
+            | GL_INT_VEC4
+            | GL_UNSIGNED_INT
+            | GL_UNSIGNED_INT_VEC2
+            | GL_UNSIGNED_INT_VEC3
+            | GL_UNSIGNED_INT_VEC4
+    )
+}
+
+unsafe fn validate_attributes(program: GLuint, attributes: &[VertexAttribute]) -> Vec<String> {
+    let active_attributes = active_variables(program, false);
+    let mut errors = vec![];
+
+    for attribute in attributes {
+        match active_attributes
+            .iter()
+            .find(|active| active.name == attribute.name)
+        {
+            None => errors.push(format!(
+                "attribute \"{}\" not found in the program",
+                attribute.name
+            )),
+            Some(active) => {
+                // missing vector components are filled by GL, so only the kind of type is checked
+                let matrix = attribute.format == VertexFormat::Mat4;
+                let valid = match active.gl_type {
+                    GL_FLOAT | GL_FLOAT_VEC2 | GL_FLOAT_VEC3 | GL_FLOAT_VEC4 => !matrix,
+                    GL_FLOAT_MAT4 => matrix,
+                    gl_type if is_integer_attribute(gl_type) => attribute.format.is_integer(),
+                    _ => false,
+                };
+                if !valid {
+                    errors.push(format!(
+                        "attribute \"{}\" is {:?} in the layout, but GL type {:#x} in the program",
+                        attribute.name, attribute.format, active.gl_type
+                    ));
+                }
+            }
+        }
+    }
+
+    errors
+}
+
+unsafe fn reflect_meta(
+    program: GLuint,
+    active_uniforms: &[ActiveVariable],
+) -> Result<ShaderMeta, ShaderError> {
+    let mut images = vec![];
+    let mut uniforms = vec![];
+
+    for uniform in active_uniforms {
+        // builtins and uniform block members
+        if uniform.name.starts_with("gl_") || get_uniform_location(program, &uniform.name).is_none()
+        {
+            continue;
+        }
+
+        if is_sampler_type(uniform.gl_type) {
+            images.push(uniform.name.clone());
+        } else if let Some(uniform_type) = UniformType::from_gl_type(uniform.gl_type) {
+            uniforms
+                .push(UniformDesc::new(&uniform.name, uniform_type).array(uniform.size as usize));
+        } else {
+            return Err(ShaderError::MetaMismatch(format!(
+                "uniform \"{}\" has GL type {:#x}, not supported by UniformType",
+                uniform.name, uniform.gl_type
+            )));
+        }
     }
+
+    images.sort();
+    uniforms.sort_by(|a, b| a.name.cmp(&b.name));
+
+    Ok(ShaderMeta {
+        images,
+        uniforms: UniformBlockLayout::new(uniforms),
+    })
 }
 
 type UniformLocation = Option<GLint>;
@@ -320,7 +981,8 @@ pub struct ShaderImage {
 #[derive(Debug)]
 pub struct ShaderUniform {
     gl_loc: UniformLocation,
-    _offset: usize,
+    offset: usize,
+    array_stride: usize,
     _size: usize,
     uniform_type: UniformType,
     array_count: i32,
@@ -328,8 +990,20 @@ pub struct ShaderUniform {
 
 struct ShaderInternal {
     program: GLuint,
+    meta: ShaderMeta,
+    /// `meta` was reflected from the program and should be reflected again on reload.
+    reflected: bool,
     images: Vec<ShaderImage>,
     uniforms: Vec<ShaderUniform>,
+    /// Expected size of the uniforms struct.
+    uniforms_size: usize,
+    /// `GL_UNIFORM_BLOCK_DATA_SIZE` of the std140 block, if the shader is using uniform buffer.
+    uniform_block_size: Option<usize>,
+    /// Set by `Shader::label`, given to the new program on reload.
+    label: Option<String>,
+    /// Vertex and fragment sources, kept with `GraphicsContext::enable_resource_registry`
+    /// to build the program again after a context loss.
+    sources: Option<(String, String)>,
 }
 
 /// Pixel arithmetic description for blending operations.
@@ -438,17 +1112,28 @@ struct GlCache {
     stored_index_buffer: GLuint,
     stored_index_type: Option<IndexType>,
     stored_vertex_buffer: GLuint,
-    stored_texture: GLuint,
+    stored_indirect_buffer: GLuint,
+    stored_texture: (GLenum, GLuint),
     index_buffer: GLuint,
     index_type: Option<IndexType>,
     vertex_buffer: GLuint,
-    textures: [GLuint; MAX_SHADERSTAGE_IMAGES],
+    indirect_buffer: GLuint,
+    /// (target, texture) bound to each texture unit.
+    textures: [(GLenum, GLuint); MAX_SHADERSTAGE_IMAGES],
+    samplers: [GLuint; MAX_SHADERSTAGE_IMAGES],
     cur_pipeline: Option<Pipeline>,
+    cur_pass: Option<RenderPass>,
     color_blend: Option<BlendState>,
     alpha_blend: Option<BlendState>,
     stencil: Option<StencilState>,
     color_write: ColorMask,
     cull_face: CullFace,
+    blend_color: (f32, f32, f32, f32),
+    polygon_mode: PolygonMode,
+    line_width: f32,
+    program_point_size: bool,
+    depth_clamp: bool,
+    alpha_to_coverage: bool,
     attributes: [Option<CachedAttribute>; MAX_VERTEX_ATTRIBUTES],
 }
 
@@ -461,6 +1146,13 @@ impl GlCache {
                     glBindBuffer(target, buffer);
                 }
             }
+        } else if target == GL_DRAW_INDIRECT_BUFFER {
+            if self.indirect_buffer != buffer {
+                self.indirect_buffer = buffer;
+                unsafe {
+                    glBindBuffer(target, buffer);
+                }
+            }
         } else {
             if self.index_buffer != buffer {
                 self.index_buffer = buffer;
@@ -475,6 +1167,8 @@ impl GlCache {
     fn store_buffer_binding(&mut self, target: GLenum) {
         if target == GL_ARRAY_BUFFER {
             self.stored_vertex_buffer = self.vertex_buffer;
+        } else if target == GL_DRAW_INDIRECT_BUFFER {
+            self.stored_indirect_buffer = self.indirect_buffer;
         } else {
             self.stored_index_buffer = self.index_buffer;
             self.stored_index_type = self.index_type;
@@ -487,6 +1181,11 @@ impl GlCache {
                 self.bind_buffer(target, self.stored_vertex_buffer, None);
                 self.stored_vertex_buffer = 0;
             }
+        } else if target == GL_DRAW_INDIRECT_BUFFER {
+            if self.stored_indirect_buffer != 0 {
+                self.bind_buffer(target, self.stored_indirect_buffer, None);
+                self.stored_indirect_buffer = 0;
+            }
         } else {
             if self.stored_index_buffer != 0 {
                 self.bind_buffer(target, self.stored_index_buffer, self.stored_index_type);
@@ -495,13 +1194,28 @@ impl GlCache {
         }
     }
 
-    fn bind_texture(&mut self, slot_index: usize, texture: GLuint) {
+    fn bind_texture(&mut self, slot_index: usize, target: GLenum, texture: GLuint) {
         unsafe {
             glActiveTexture(GL_TEXTURE0 + slot_index as GLuint);
-            if self.textures[slot_index] != texture {
-                glBindTexture(GL_TEXTURE_2D, texture);
-                self.textures[slot_index] = texture;
+            let (cur_target, cur_texture) = self.textures[slot_index];
+            if (cur_target, cur_texture) != (target, texture) {
+                // texture unit has a binding point per target,
+                // only one of them is kept non-zero
+                if cur_target != target && cur_texture != 0 {
+                    glBindTexture(cur_target, 0);
+                }
+                glBindTexture(target, texture);
+                self.textures[slot_index] = (target, texture);
+            }
+        }
+    }
+
+    fn bind_sampler(&mut self, slot_index: usize, sampler: GLuint) {
+        if self.samplers[slot_index] != sampler {
+            unsafe {
+                glBindSampler(slot_index as GLuint, sampler);
             }
+            self.samplers[slot_index] = sampler;
         }
     }
 
@@ -510,7 +1224,8 @@ impl GlCache {
     }
 
     fn restore_texture_binding(&mut self, slot_index: usize) {
-        self.bind_texture(slot_index, self.stored_texture);
+        let (target, texture) = self.stored_texture;
+        self.bind_texture(slot_index, target, texture);
     }
 
     fn clear_buffer_bindings(&mut self) {
@@ -519,13 +1234,18 @@ impl GlCache {
 
         self.bind_buffer(GL_ELEMENT_ARRAY_BUFFER, 0, None);
         self.index_buffer = 0;
+
+        // the target does not exist without indirect drawing support
+        if self.indirect_buffer != 0 {
+            self.bind_buffer(GL_DRAW_INDIRECT_BUFFER, 0, None);
+        }
     }
 
     fn clear_texture_bindings(&mut self) {
         for ix in 0..MAX_SHADERSTAGE_IMAGES {
-            if self.textures[ix] != 0 {
-                self.bind_texture(ix, 0);
-                self.textures[ix] = 0;
+            let (target, texture) = self.textures[ix];
+            if texture != 0 {
+                self.bind_texture(ix, target, 0);
             }
         }
     }
@@ -538,6 +1258,16 @@ pub enum PassAction {
         depth: Option<f32>,
         stencil: Option<i32>,
     },
+    /// Clear each color attachment of a multiple render targets pass
+    /// with its own color.
+    /// `colors[n]` is used for the n-th color attachment, None or missing
+    /// entries leave the attachment untouched.
+    /// On the default framebuffer only `colors[0]` is used.
+    ClearMrt {
+        colors: Vec<Option<(f32, f32, f32, f32)>>,
+        depth: Option<f32>,
+        stencil: Option<i32>,
+    },
 }
 
 impl PassAction {
@@ -561,12 +1291,16 @@ impl Default for PassAction {
 }
 
 #[derive(Clone, Copy, Debug, PartialEq)]
-pub struct RenderPass(usize);
+pub struct RenderPass(SlotId);
 
 struct RenderPassInternal {
     gl_fb: GLuint,
-    texture: Texture,
+    textures: Vec<Texture>,
     depth_texture: Option<Texture>,
+    /// Multisampled passes draw into `renderbuffers` attached to `gl_fb`
+    /// and get resolved into `resolve_fb`, the framebuffer with the textures.
+    resolve_fb: Option<GLuint>,
+    renderbuffers: Vec<GLuint>,
 }
 
 impl RenderPass {
@@ -575,81 +1309,802 @@ impl RenderPass {
         color_img: Texture,
         depth_img: impl Into<Option<Texture>>,
     ) -> RenderPass {
-        let mut gl_fb = 0;
+        Self::new_mrt(ctx, &[color_img], depth_img)
+    }
+
+    /// Multisampled render pass.
+    /// Rendering goes to multisample renderbuffers, `color_img` and `depth_img`
+    /// receive the resolved result on `end_render_pass`.
+    ///
+    /// `sample_count` is clamped to `Features::max_msaa_samples`,
+    /// with 1 sample this is just a `RenderPass::new`.
+    pub fn new_msaa(
+        ctx: &mut Context,
+        color_img: Texture,
+        depth_img: impl Into<Option<Texture>>,
+        sample_count: i32,
+    ) -> RenderPass {
+        Self::new_mrt_msaa(ctx, &[color_img], depth_img, sample_count)
+    }
+
+    /// Render pass with multiple render targets.
+    /// `color_imgs[n]` is attached to `GL_COLOR_ATTACHMENTn` and is written by the n-th
+    /// fragment shader output.
+    ///
+    /// NOTE: more than one color attachment is not supported on gl2.1, gles2 and
+    /// webgl1 without `WEBGL_draw_buffers`.
+    pub fn new_mrt(
+        ctx: &mut Context,
+        color_imgs: &[Texture],
+        depth_img: impl Into<Option<Texture>>,
+    ) -> RenderPass {
+        Self::new_mrt_msaa(ctx, color_imgs, depth_img, 1)
+    }
+
+    /// Multisampled render pass with multiple render targets,
+    /// see `RenderPass::new_mrt` and `RenderPass::new_msaa`.
+    pub fn new_mrt_msaa(
+        ctx: &mut Context,
+        color_imgs: &[Texture],
+        depth_img: impl Into<Option<Texture>>,
+        sample_count: i32,
+    ) -> RenderPass {
+        Self::create(ctx, color_imgs, 0, depth_img.into(), sample_count)
+    }
+
+    /// Render pass drawing into one layer of `color_img`: a face of a cube map
+    /// (`CubeFace::PositiveX as u32` etc), a layer of a 2D array or a slice of a 3D texture.
+    /// `depth_img` should be a regular 2D texture.
+    pub fn new_layer(
+        ctx: &mut Context,
+        color_img: Texture,
+        layer: u32,
+        depth_img: impl Into<Option<Texture>>,
+    ) -> RenderPass {
+        Self::create(ctx, &[color_img], layer, depth_img.into(), 1)
+    }
+
+    fn create(
+        ctx: &mut Context,
+        color_imgs: &[Texture],
+        color_layer: u32,
+        depth_img: Option<Texture>,
+        sample_count: i32,
+    ) -> RenderPass {
+        assert!(
+            !color_imgs.is_empty(),
+            "RenderPass needs at least one color attachment"
+        );
+
+        let sample_count = sample_count.min(ctx.features.max_msaa_samples);
 
-        let depth_img = depth_img.into();
+        let attachments: Vec<(GLenum, Texture, u32)> = color_imgs
+            .iter()
+            .enumerate()
+            .map(|(n, img)| (GL_COLOR_ATTACHMENT0 + n as GLenum, *img, color_layer))
+            .chain(depth_img.map(|img| {
+                let attachment = img.format.depth_attachment().unwrap_or(GL_DEPTH_ATTACHMENT);
+                (attachment, img, 0)
+            }))
+            .collect();
+
+        let mut gl_fb = 0;
+        let mut resolve_fb = None;
+        let mut renderbuffers = vec![];
 
         unsafe {
-            glGenFramebuffers(1, &mut gl_fb as *mut _);
-            glBindFramebuffer(GL_FRAMEBUFFER, gl_fb);
-            glFramebufferTexture2D(
-                GL_FRAMEBUFFER,
-                GL_COLOR_ATTACHMENT0,
-                GL_TEXTURE_2D,
-                color_img.texture,
-                0,
-            );
-            if let Some(depth_img) = depth_img {
-                glFramebufferTexture2D(
-                    GL_FRAMEBUFFER,
-                    GL_DEPTH_ATTACHMENT,
-                    GL_TEXTURE_2D,
-                    depth_img.texture,
-                    0,
-                );
+            let mut textures_fb = 0;
+            glGenFramebuffers(1, &mut textures_fb as *mut _);
+            glBindFramebuffer(GL_FRAMEBUFFER, textures_fb);
+            for (attachment, img, layer) in &attachments {
+                img.attach_to_framebuffer(*attachment, *layer);
+            }
+            enable_draw_buffers(color_imgs.len());
+
+            if sample_count > 1 {
+                glGenFramebuffers(1, &mut gl_fb as *mut _);
+                glBindFramebuffer(GL_FRAMEBUFFER, gl_fb);
+                for (attachment, img, _) in &attachments {
+                    let mut renderbuffer = 0;
+                    glGenRenderbuffers(1, &mut renderbuffer as *mut _);
+                    glBindRenderbuffer(GL_RENDERBUFFER, renderbuffer);
+                    glRenderbufferStorageMultisample(
+                        GL_RENDERBUFFER,
+                        sample_count,
+                        img.format.renderbuffer_format(),
+                        img.width as _,
+                        img.height as _,
+                    );
+                    glFramebufferRenderbuffer(
+                        GL_FRAMEBUFFER,
+                        *attachment,
+                        GL_RENDERBUFFER,
+                        renderbuffer,
+                    );
+                    renderbuffers.push(renderbuffer);
+                }
+                glBindRenderbuffer(GL_RENDERBUFFER, 0);
+                enable_draw_buffers(color_imgs.len());
+                resolve_fb = Some(textures_fb);
+            } else {
+                gl_fb = textures_fb;
             }
             glBindFramebuffer(GL_FRAMEBUFFER, ctx.default_framebuffer);
         }
         let pass = RenderPassInternal {
             gl_fb,
-            texture: color_img,
+            textures: color_imgs.to_vec(),
             depth_texture: depth_img,
+            resolve_fb,
+            renderbuffers,
         };
 
-        ctx.passes.push(pass);
-
-        RenderPass(ctx.passes.len() - 1)
+        RenderPass(ctx.passes.insert(pass))
     }
 
+    /// First color attachment of the pass.
     pub fn texture(&self, ctx: &mut Context) -> Texture {
+        self.texture_at(ctx, 0)
+    }
+
+    /// Color attachment attached to `GL_COLOR_ATTACHMENT0 + index`.
+    pub fn texture_at(&self, ctx: &mut Context, index: usize) -> Texture {
         let render_pass = &mut ctx.passes[self.0];
+        assert!(
+            index < render_pass.textures.len(),
+            "RenderPass has {} color attachments",
+            render_pass.textures.len()
+        );
+
+        render_pass.textures[index]
+    }
+
+    /// Number of color attachments of the pass.
+    pub fn color_attachments_count(&self, ctx: &mut Context) -> usize {
+        ctx.passes[self.0].textures.len()
+    }
 
-        render_pass.texture
+    /// Name the framebuffer, and the resolve one of a multisampled pass, in driver messages
+    /// and frame debuggers. Does nothing without `features.debug_output`.
+    pub fn label(&self, ctx: &mut Context, label: &str) {
+        let pass = &ctx.passes[self.0];
+        ctx.object_label(GL_FRAMEBUFFER, pass.gl_fb, label);
+        if let Some(resolve_fb) = pass.resolve_fb {
+            ctx.object_label(GL_FRAMEBUFFER, resolve_fb, label);
+        }
     }
 
+    /// Delete the framebuffer with its attachments and free the handle.
+    /// Using the pass afterwards panics.
     pub fn delete(&self, ctx: &mut Context) {
-        let render_pass = &mut ctx.passes[self.0];
+        let mut render_pass = ctx
+            .passes
+            .remove(self.0)
+            .expect("RenderPass used after delete");
 
-        unsafe { glDeleteFramebuffers(1, &mut render_pass.gl_fb as *mut _) }
+        unsafe {
+            glDeleteFramebuffers(1, &mut render_pass.gl_fb as *mut _);
+            if let Some(resolve_fb) = render_pass.resolve_fb {
+                glDeleteFramebuffers(1, &resolve_fb as *const _);
+            }
+            if !render_pass.renderbuffers.is_empty() {
+                glDeleteRenderbuffers(
+                    render_pass.renderbuffers.len() as _,
+                    render_pass.renderbuffers.as_ptr(),
+                );
+            }
+        }
 
-        render_pass.texture.delete();
+        for texture in &render_pass.textures {
+            texture.delete();
+        }
         if let Some(depth_texture) = render_pass.depth_texture {
             depth_texture.delete();
         }
     }
 }
 
-pub const MAX_VERTEX_ATTRIBUTES: usize = 16;
-pub const MAX_SHADERSTAGE_IMAGES: usize = 12;
-
-pub struct Features {
-    pub instancing: bool,
+fn color_attachments(count: usize) -> Vec<GLenum> {
+    (0..count)
+        .map(|n| GL_COLOR_ATTACHMENT0 + n as GLenum)
+        .collect()
 }
 
-impl Features {
-    pub fn from_gles2(is_gles2: bool) -> Self {
-        Features {
-            instancing: !is_gles2,
-        }
+/// Enable all the color attachments of the currently bound framebuffer.
+/// Framebuffer with a single attachment is fine with the default draw buffer.
+unsafe fn enable_draw_buffers(count: usize) {
+    if count > 1 {
+        let draw_buffers = color_attachments(count);
+        glDrawBuffers(draw_buffers.len() as _, draw_buffers.as_ptr());
     }
 }
 
-pub struct GraphicsContext {
-    shaders: Vec<ShaderInternal>,
-    pipelines: Vec<PipelineInternal>,
-    passes: Vec<RenderPassInternal>,
-    default_framebuffer: GLuint,
-    cache: GlCache,
+pub const MAX_VERTEX_ATTRIBUTES: usize = 16;
+pub const MAX_SHADERSTAGE_IMAGES: usize = 12;
+
+/// All the std140 blocks are bound to the same point, one block per shader.
+const UNIFORM_BLOCK_BINDING: GLuint = 0;
+const UNIFORM_STREAM_SIZE: usize = 64 * 1024;
+
+/// Uniform buffer shared by all the shaders with std140 blocks.
+/// Each `apply_uniforms` appends to the buffer and binds the new range,
+/// when the buffer is full it is orphaned and written from the start again.
+struct UniformStream {
+    gl_buf: GLuint,
+    offset: usize,
+    alignment: usize,
+}
+
+impl UniformStream {
+    unsafe fn push(&mut self, data: *const u8, size: usize, block_size: usize) {
+        if self.gl_buf == 0 {
+            let mut alignment = 0;
+            glGetIntegerv(GL_UNIFORM_BUFFER_OFFSET_ALIGNMENT, &mut alignment as *mut _);
+            self.alignment = alignment.max(1) as usize;
+            glGenBuffers(1, &mut self.gl_buf as *mut _);
+            glBindBuffer(GL_UNIFORM_BUFFER, self.gl_buf);
+            glBufferData(
+                GL_UNIFORM_BUFFER,
+                UNIFORM_STREAM_SIZE as _,
+                std::ptr::null(),
+                GL_STREAM_DRAW,
+            );
+        } else {
+            glBindBuffer(GL_UNIFORM_BUFFER, self.gl_buf);
+        }
+
+        let block_size = block_size.max(size);
+        assert!(
+            block_size <= UNIFORM_STREAM_SIZE,
+            "Uniform block is too big"
+        );
+        let mut offset = self.offset.div_ceil(self.alignment) * self.alignment;
+        if offset + block_size > UNIFORM_STREAM_SIZE {
+            glBufferData(
+                GL_UNIFORM_BUFFER,
+                UNIFORM_STREAM_SIZE as _,
+                std::ptr::null(),
+                GL_STREAM_DRAW,
+            );
+            offset = 0;
+        }
+        glBufferSubData(GL_UNIFORM_BUFFER, offset as _, size as _, data as _);
+        glBindBufferRange(
+            GL_UNIFORM_BUFFER,
+            UNIFORM_BLOCK_BINDING,
+            self.gl_buf,
+            offset as _,
+            block_size as _,
+        );
+        self.offset = offset + block_size;
+    }
+}
+
+/// Kind of the context `GraphicsContext` runs on.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum GlApi {
+    Gl,
+    Gles,
+    WebGl,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum GlProfile {
+    /// gl3.2+ core profile, the only gl3 one on macos.
+    Core,
+    /// gl3.2+ compatibility profile, or any gl below 3.2.
+    Compatibility,
+    /// gles and webgl have no profiles.
+    Es,
+}
+
+/// What the current context is and what it can do, queried once in `GraphicsContext::new`.
+#[derive(Clone, Debug)]
+pub struct Features {
+    pub api: GlApi,
+    /// (major, minor) of the `api`, (1, 0) for webgl1.
+    pub version: (u32, u32),
+    pub profile: GlProfile,
+    /// GL_VERSION as reported by the driver, with the driver version on most platforms.
+    pub version_string: String,
+    /// GL_VENDOR, usually names the driver, not the GPU.
+    pub vendor: String,
+    /// GL_RENDERER, usually names the GPU.
+    pub renderer: String,
+    /// Every extension the context reports, see `has_extension`.
+    pub extensions: Vec<String>,
+    /// Maximum width and height of a 2D texture.
+    pub max_texture_size: i32,
+    /// Maximum `RenderPass::new_mrt` attachments, 1 on gl2.1, gles2 and webgl1
+    /// without `WEBGL_draw_buffers`.
+    pub max_color_attachments: i32,
+    /// GL_MAX_VERTEX_ATTRIBS, pipelines are also limited to `MAX_VERTEX_ATTRIBUTES`.
+    pub max_vertex_attributes: i32,
+    /// Fragment shader texture units, shaders are also limited to `MAX_SHADERSTAGE_IMAGES`.
+    pub max_texture_units: i32,
+    pub instancing: bool,
+    /// Maximum sample count for `RenderPass::new_msaa`.
+    /// 1 if multisampled render passes are not supported (gl2.1, gles2 and webgl1).
+    pub max_msaa_samples: i32,
+    /// Texture formats that can't be used at all with the current context.
+    pub unsupported_texture_formats: Vec<TextureFormat>,
+    /// Texture formats that can be sampled, but can't be a `RenderPass` attachment.
+    pub unrenderable_texture_formats: Vec<TextureFormat>,
+    /// `Sampler` is a GL sampler object. If false, samplers are emulated
+    /// with texture parameters (gles2 and webgl1).
+    pub sampler_objects: bool,
+    /// Maximum `SamplerParams::max_anisotropy`, 1.0 without `EXT_texture_filter_anisotropic`.
+    pub max_anisotropy: f32,
+    /// std140 uniform blocks are uploaded through a uniform buffer.
+    /// If false, `UniformBlockLayout::std140_block` uniforms are set one by one (gles2 and webgl1).
+    pub uniform_buffers: bool,
+    /// Dialect `Preprocessor` translates shaders to.
+    pub glsl_version: GlslVersion,
+    /// Linked programs can be saved and loaded back as driver specific binaries.
+    /// `conf::Conf::program_cache` is ignored otherwise (gles2, webgl1 and gl < 4.1
+    /// without `ARB_get_program_binary`).
+    pub program_binary: bool,
+    /// `draw_base_vertex` is a single glDrawElementsBaseVertex call (gl3.2 and gles3.2).
+    /// Otherwise the vertex attribute pointers are offset around the draw call.
+    pub base_vertex: bool,
+    /// `draw_multi` is a single glMultiDrawElements call (desktop gl only).
+    /// Otherwise the ranges are drawn one by one.
+    pub multi_draw: bool,
+    /// `BufferType::IndirectBuffer` and `draw_indirect` are available (gl4.0 and gles3.1).
+    pub draw_indirect: bool,
+    /// `draw_indirect` of many commands is a single glMultiDrawElementsIndirect call
+    /// (gl4.3, not on macos). Otherwise the commands are drawn one by one.
+    pub multi_draw_indirect: bool,
+    /// `RingBuffer` writes through glMapBufferRange (not on gles2 and webgl1).
+    /// Otherwise it falls back to glBufferSubData.
+    pub buffer_mapping: bool,
+    /// `RingBuffer` storage is mapped once for its whole lifetime (gl4.4, not on macos).
+    pub persistent_mapping: bool,
+    /// Maximum `BufferLayout::stride`, as well as the stride of a buffer with
+    /// the attributes packed. 255 on webgl1, queried on gl4.4 and gles3.1,
+    /// `i32::MAX` otherwise as older contexts have no limit.
+    pub max_vertex_attrib_stride: i32,
+    /// `QueryType::AnySamplesPassed` queries (gl3.3, gles3, `EXT_occlusion_query_boolean`).
+    pub occlusion_query: bool,
+    /// `QueryType::TimeElapsed` queries and `ElapsedQuery` (gl3.3, `EXT_disjoint_timer_query`
+    /// on gles and webgl, not on ios).
+    pub timer_query: bool,
+    /// Timer results may be invalidated by a disjoint operation like a GPU frequency change,
+    /// see `Context::gpu_disjoint` (gles and webgl).
+    pub timer_disjoint: bool,
+    /// `QueryType::Timestamp` queries and `GpuProfiler`. Timer queries without timestamps
+    /// are allowed on gles and webgl.
+    pub timestamp_query: bool,
+    /// `QueryType::VerticesSubmitted` and the other pipeline statistics (gl4.6 or
+    /// `ARB_pipeline_statistics_query`, desktop only).
+    pub pipeline_statistics_query: bool,
+    /// `Buffer::resize` keeps the contents with a GPU side glCopyBufferSubData
+    /// (not on gles2 and webgl1). Otherwise `stream` and `dynamic` buffers keep
+    /// a CPU copy of everything written to them.
+    pub copy_buffer: bool,
+    /// Driver messages, object labels and debug groups of KHR_debug (gl4.3, gles3.2,
+    /// not on macos and webgl). Without it `label`, `push_debug_group` and
+    /// `pop_debug_group` do nothing.
+    pub debug_output: bool,
+    /// `PipelineParams::polygon_mode` other than `Fill` (desktop gl only).
+    pub polygon_mode: bool,
+    /// `PipelineParams::depth_clamp` (gl3.2, `EXT_depth_clamp` on gles, not on webgl).
+    pub depth_clamp: bool,
+    /// (min, max) `PipelineParams::line_width`, wider lines are clamped.
+    /// (1.0, 1.0) in forward-compatible contexts (macos, the wgl core context),
+    /// which reject wide lines.
+    pub line_width_range: (f32, f32),
+}
+
+/// Texture formats outside of the gl3.3 core and webgl1 intersection.
+const EXTENDED_TEXTURE_FORMATS: [TextureFormat; 7] = [
+    TextureFormat::RGBA16F,
+    TextureFormat::RGBA32F,
+    TextureFormat::R32F,
+    TextureFormat::RG8,
+    TextureFormat::R16F,
+    TextureFormat::SRGB8_ALPHA8,
+    TextureFormat::Depth24Stencil8,
+];
+
+const COMPRESSED_TEXTURE_FORMATS: [TextureFormat; 7] = [
+    TextureFormat::BC1,
+    TextureFormat::BC2,
+    TextureFormat::BC3,
+    TextureFormat::ETC2RGB8,
+    TextureFormat::ETC2RGBA8,
+    TextureFormat::ASTC4x4,
+    TextureFormat::ASTC8x8,
+];
+
+impl Features {
+    pub fn from_gles2(is_gles2: bool) -> Self {
+        // webgl1 context is not reported as gles2, but has none of the extended formats either
+        let mut unsupported_texture_formats = if is_gles2 || cfg!(target_arch = "wasm32") {
+            EXTENDED_TEXTURE_FORMATS.to_vec()
+        } else {
+            vec![]
+        };
+        // compressed formats depend on extensions, unknown until the context is queried
+        unsupported_texture_formats.extend(&COMPRESSED_TEXTURE_FORMATS);
+
+        // spec minimums until the context is queried
+        Features {
+            api: if cfg!(target_arch = "wasm32") {
+                GlApi::WebGl
+            } else if is_gles2 {
+                GlApi::Gles
+            } else {
+                GlApi::Gl
+            },
+            version: if cfg!(target_arch = "wasm32") {
+                (1, 0)
+            } else if is_gles2 {
+                (2, 0)
+            } else {
+                (3, 3)
+            },
+            profile: if is_gles2 || cfg!(target_arch = "wasm32") {
+                GlProfile::Es
+            } else {
+                GlProfile::Core
+            },
+            version_string: String::new(),
+            vendor: String::new(),
+            renderer: String::new(),
+            extensions: vec![],
+            max_texture_size: if is_gles2 { 64 } else { 1024 },
+            max_color_attachments: if is_gles2 { 1 } else { 8 },
+            max_vertex_attributes: if is_gles2 { 8 } else { 16 },
+            max_texture_units: if is_gles2 { 8 } else { 16 },
+            instancing: !is_gles2,
+            max_msaa_samples: 1,
+            unsupported_texture_formats,
+            unrenderable_texture_formats: vec![],
+            sampler_objects: !is_gles2 && cfg!(not(target_arch = "wasm32")),
+            max_anisotropy: 1.,
+            uniform_buffers: !is_gles2 && cfg!(not(target_arch = "wasm32")),
+            glsl_version: if is_gles2 || cfg!(target_arch = "wasm32") {
+                GlslVersion::Glsl100
+            } else {
+                GlslVersion::Glsl330
+            },
+            program_binary: false,
+            base_vertex: false,
+            multi_draw: false,
+            draw_indirect: false,
+            multi_draw_indirect: false,
+            buffer_mapping: !is_gles2 && cfg!(not(target_arch = "wasm32")),
+            persistent_mapping: false,
+            occlusion_query: false,
+            timer_query: false,
+            timer_disjoint: false,
+            timestamp_query: false,
+            pipeline_statistics_query: false,
+            copy_buffer: !is_gles2 && cfg!(not(target_arch = "wasm32")),
+            debug_output: false,
+            polygon_mode: false,
+            depth_clamp: false,
+            line_width_range: (1., 1.),
+            max_vertex_attrib_stride: if cfg!(target_arch = "wasm32") {
+                255
+            } else {
+                i32::MAX
+            },
+        }
+    }
+
+    /// Whether `name`, like "GL_KHR_debug" or "WEBGL_draw_buffers", is in `extensions`.
+    pub fn has_extension(&self, name: &str) -> bool {
+        self.extensions.iter().any(|ext| ext == name)
+    }
+
+    /// Whether textures of `format` can be created and sampled.
+    pub fn is_texture_format_supported(&self, format: TextureFormat) -> bool {
+        !self.unsupported_texture_formats.contains(&format)
+    }
+
+    /// Compressed formats can be sampled when supported, but never rendered to.
+    fn set_compressed_support(&mut self, s3tc: bool, etc2: bool, astc: bool) {
+        let supported = |format: &TextureFormat| match format {
+            TextureFormat::BC1 | TextureFormat::BC2 | TextureFormat::BC3 => s3tc,
+            TextureFormat::ETC2RGB8 | TextureFormat::ETC2RGBA8 => etc2,
+            TextureFormat::ASTC4x4 | TextureFormat::ASTC8x8 => astc,
+            _ => false,
+        };
+        self.unsupported_texture_formats
+            .retain(|format| !supported(format));
+        self.unrenderable_texture_formats.extend(
+            COMPRESSED_TEXTURE_FORMATS
+                .iter()
+                .filter(|format| supported(format)),
+        );
+    }
+}
+
+unsafe fn gl_string(name: GLenum) -> String {
+    let string = glGetString(name);
+    if string.is_null() {
+        return String::new();
+    }
+    std::ffi::CStr::from_ptr(string as _)
+        .to_string_lossy()
+        .into_owned()
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+unsafe fn gl_extensions(is_gles2: bool) -> Vec<String> {
+    // glGetStringi is gl3+, while gl3 core profile has no GL_EXTENSIONS string
+    if is_gles2 {
+        return gl_string(GL_EXTENSIONS)
+            .split_whitespace()
+            .map(|ext| ext.to_owned())
+            .collect();
+    }
+
+    let mut count = 0;
+    glGetIntegerv(GL_NUM_EXTENSIONS, &mut count as *mut _);
+    (0..count.max(0) as GLuint)
+        .map(|n| {
+            std::ffi::CStr::from_ptr(glGetStringi(GL_EXTENSIONS, n) as _)
+                .to_string_lossy()
+                .into_owned()
+        })
+        .collect()
+}
+
+/// (major, minor) from a GL_VERSION string like "4.6 (Core Profile) Mesa 22.3.6",
+/// "OpenGL ES 3.2 Mesa 22.3.6" or "WebGL 1.0 (OpenGL ES 2.0 Chromium)".
+fn parse_gl_version(version: &str) -> (u32, u32) {
+    let mut numbers = version
+        .split(|c: char| !c.is_ascii_digit())
+        .filter(|n| !n.is_empty())
+        .map(|n| n.parse().unwrap_or(0));
+    (numbers.next().unwrap_or(0), numbers.next().unwrap_or(0))
+}
+
+/// EXT_disjoint_timer_query allows a zero bit timestamp counter, meaning no timestamps.
+unsafe fn timestamp_bits() -> GLint {
+    let mut bits = 0;
+    glGetQueryiv(GL_TIMESTAMP, GL_QUERY_COUNTER_BITS, &mut bits as *mut _);
+    bits
+}
+
+unsafe fn line_width_range() -> (f32, f32) {
+    let mut range = [1.0f32; 2];
+    glGetFloatv(GL_ALIASED_LINE_WIDTH_RANGE, range.as_mut_ptr());
+    (range[0], range[1])
+}
+
+/// `Features::from_gles2` refined with the current context queries.
+unsafe fn query_features(is_gles2: bool) -> Features {
+    let mut features = Features::from_gles2(is_gles2);
+
+    features.version_string = gl_string(GL_VERSION);
+    features.vendor = gl_string(GL_VENDOR);
+    features.renderer = gl_string(GL_RENDERER);
+    features.version = parse_gl_version(&features.version_string);
+    glGetIntegerv(
+        GL_MAX_TEXTURE_SIZE,
+        &mut features.max_texture_size as *mut _,
+    );
+    glGetIntegerv(
+        GL_MAX_VERTEX_ATTRIBS,
+        &mut features.max_vertex_attributes as *mut _,
+    );
+    glGetIntegerv(
+        GL_MAX_TEXTURE_IMAGE_UNITS,
+        &mut features.max_texture_units as *mut _,
+    );
+
+    #[cfg(not(target_arch = "wasm32"))]
+    {
+        let version = features.version_string.clone();
+        let gles = version.starts_with("OpenGL ES");
+        let gl_version = features.version;
+        if !is_gles2 {
+            features.glsl_version = GlslVersion::from_gl_version(&version);
+        }
+        let extensions = gl_extensions(is_gles2);
+        let has_extension = |name: &str| extensions.iter().any(|ext| ext == name);
+
+        features.api = if gles { GlApi::Gles } else { GlApi::Gl };
+        features.profile = if gles {
+            GlProfile::Es
+        } else if gl_version >= (3, 2) {
+            let mut mask = 0;
+            glGetIntegerv(GL_CONTEXT_PROFILE_MASK, &mut mask as *mut _);
+            if mask as GLenum & GL_CONTEXT_CORE_PROFILE_BIT != 0 {
+                GlProfile::Core
+            } else {
+                GlProfile::Compatibility
+            }
+        } else {
+            GlProfile::Compatibility
+        };
+
+        if !is_gles2 {
+            glGetIntegerv(GL_MAX_SAMPLES, &mut features.max_msaa_samples as *mut _);
+
+            // glDrawBuffers may allow less buffers than there are attachment points
+            let mut attachments = 0;
+            let mut draw_buffers = 0;
+            glGetIntegerv(GL_MAX_COLOR_ATTACHMENTS, &mut attachments as *mut _);
+            glGetIntegerv(GL_MAX_DRAW_BUFFERS, &mut draw_buffers as *mut _);
+            features.max_color_attachments = attachments.min(draw_buffers);
+
+            // float formats are color-renderable on gl3, but gles3 needs an extension
+            if gles {
+                let float = has_extension("GL_EXT_color_buffer_float");
+                let half_float = float || has_extension("GL_EXT_color_buffer_half_float");
+                if !float {
+                    features
+                        .unrenderable_texture_formats
+                        .extend(&[TextureFormat::RGBA32F, TextureFormat::R32F]);
+                }
+                if !half_float {
+                    features
+                        .unrenderable_texture_formats
+                        .extend(&[TextureFormat::RGBA16F, TextureFormat::R16F]);
+                }
+            }
+        }
+
+        if gl_version >= if gles { (3, 1) } else { (4, 4) } {
+            glGetIntegerv(
+                GL_MAX_VERTEX_ATTRIB_STRIDE,
+                &mut features.max_vertex_attrib_stride as *mut _,
+            );
+        }
+
+        // core in gles3 and gl4.1. gles2 has OES_get_program_binary, but with
+        // the OES suffixed entry points
+        if !is_gles2 && (gles || has_extension("GL_ARB_get_program_binary") || gl_version >= (4, 1))
+        {
+            let mut formats = 0;
+            glGetIntegerv(GL_NUM_PROGRAM_BINARY_FORMATS, &mut formats as *mut _);
+            features.program_binary = formats > 0;
+        }
+
+        // ios OpenGLES has none of these, the calls are not even linked there
+        if cfg!(not(target_os = "ios")) {
+            features.base_vertex = if gles {
+                version.starts_with("OpenGL ES 3.2")
+            } else {
+                gl_version >= (3, 2) || has_extension("GL_ARB_draw_elements_base_vertex")
+            };
+            features.multi_draw = !gles;
+            features.draw_indirect = if gles {
+                !is_gles2 && !version.starts_with("OpenGL ES 3.0")
+            } else {
+                gl_version >= (4, 0) || has_extension("GL_ARB_draw_indirect")
+            };
+        }
+        features.occlusion_query = if gles {
+            !is_gles2 || has_extension("GL_EXT_occlusion_query_boolean")
+        } else {
+            gl_version >= (3, 3) || has_extension("GL_ARB_occlusion_query2")
+        };
+        // ios has no 64 bit query results at all
+        if cfg!(not(target_os = "ios")) {
+            features.timer_query = if gles {
+                has_extension("GL_EXT_disjoint_timer_query")
+            } else {
+                gl_version >= (3, 3) || has_extension("GL_ARB_timer_query")
+            };
+            features.timer_disjoint = gles && features.timer_query;
+            features.timestamp_query = features.timer_query && timestamp_bits() > 0;
+            features.pipeline_statistics_query = !gles
+                && (gl_version >= (4, 6) || has_extension("GL_ARB_pipeline_statistics_query"));
+        }
+
+        // ios and macos bindings have no KHR_debug entry points at all
+        if cfg!(not(any(target_os = "ios", target_os = "macos"))) {
+            features.debug_output =
+                has_extension("GL_KHR_debug") || gl_version >= if gles { (3, 2) } else { (4, 3) };
+        }
+
+        // and there is no glPolygonMode on ios at all
+        features.polygon_mode = !gles && cfg!(not(target_os = "ios"));
+        features.depth_clamp = if gles {
+            has_extension("GL_EXT_depth_clamp")
+        } else {
+            gl_version >= (3, 2) || has_extension("GL_ARB_depth_clamp")
+        };
+        let forward_compatible = !gles && gl_version >= (3, 0) && {
+            let mut flags = 0;
+            glGetIntegerv(GL_CONTEXT_FLAGS, &mut flags as *mut _);
+            flags as GLenum & GL_CONTEXT_FLAG_FORWARD_COMPATIBLE_BIT != 0
+        };
+        if !forward_compatible {
+            features.line_width_range = line_width_range();
+        }
+
+        // macos stops at gl4.1, gles has only the EXT suffixed entry points
+        if cfg!(not(any(target_os = "ios", target_os = "macos"))) && !gles {
+            features.multi_draw_indirect =
+                gl_version >= (4, 3) || has_extension("GL_ARB_multi_draw_indirect");
+            features.persistent_mapping =
+                gl_version >= (4, 4) || has_extension("GL_ARB_buffer_storage");
+        }
+
+        if has_extension("GL_EXT_texture_filter_anisotropic")
+            || has_extension("GL_ARB_texture_filter_anisotropic")
+        {
+            glGetFloatv(
+                GL_MAX_TEXTURE_MAX_ANISOTROPY_EXT,
+                &mut features.max_anisotropy as *mut _,
+            );
+        }
+
+        features.set_compressed_support(
+            has_extension("GL_EXT_texture_compression_s3tc"),
+            // ETC2 is core in gles3 and gl4.3
+            (gles && !is_gles2)
+                || has_extension("GL_ARB_ES3_compatibility")
+                || has_extension("GL_OES_compressed_ETC2_RGBA8_texture"),
+            // and ASTC is core in gles3.2
+            version.starts_with("OpenGL ES 3.2")
+                || has_extension("GL_KHR_texture_compression_astc_ldr"),
+        );
+
+        features.extensions = extensions;
+    }
+
+    // webgl1 has no multisampled renderbuffers and no float formats,
+    // only compressed textures are up to the browser
+    #[cfg(target_arch = "wasm32")]
+    {
+        features.extensions = gl_string(GL_EXTENSIONS)
+            .split_whitespace()
+            .map(|ext| ext.to_owned())
+            .collect();
+        let has_extension =
+            |name: &str| crate::native::wasm::webgl_extension_supported(name.as_ptr(), name.len());
+        // enabled by gl.js on startup, replacing glDrawBuffers
+        if has_extension("WEBGL_draw_buffers") {
+            glGetIntegerv(
+                GL_MAX_COLOR_ATTACHMENTS,
+                &mut features.max_color_attachments as *mut _,
+            );
+        }
+        if has_extension("EXT_texture_filter_anisotropic") {
+            glGetFloatv(
+                GL_MAX_TEXTURE_MAX_ANISOTROPY_EXT,
+                &mut features.max_anisotropy as *mut _,
+            );
+        }
+        features.line_width_range = line_width_range();
+        features.timer_query = has_extension("EXT_disjoint_timer_query");
+        features.timer_disjoint = features.timer_query;
+        features.timestamp_query = features.timer_query && timestamp_bits() > 0;
+        features.set_compressed_support(
+            has_extension("WEBGL_compressed_texture_s3tc"),
+            has_extension("WEBGL_compressed_texture_etc"),
+            has_extension("WEBGL_compressed_texture_astc"),
+        );
+    }
+
+    features
+}
+
+pub struct GraphicsContext {
+    shaders: SlotMap<ShaderInternal>,
+    pipelines: SlotMap<PipelineInternal>,
+    passes: SlotMap<RenderPassInternal>,
+    default_framebuffer: GLuint,
+    cache: GlCache,
+    uniform_stream: UniformStream,
+    program_cache: Option<ProgramCache>,
+    /// (own state, sampler state) of textures with an emulated `Sampler` written into them,
+    /// only without `features.sampler_objects`.
+    emulated_samplers: HashMap<GLuint, (TextureSampling, TextureSampling)>,
+    /// Receives the driver messages once `set_debug_output` enabled them. Boxed twice
+    /// to give the driver a thin pointer that survives moving the context.
+    debug_callback: Option<Box<debug::DebugCallback>>,
+    debug_output: bool,
+    resource_registry: Option<ResourceRegistry>,
+    meta_validation: bool,
 
     pub(crate) features: Features,
     pub(crate) display: Option<*mut dyn crate::NativeDisplay>,
@@ -667,29 +2122,54 @@ impl GraphicsContext {
 
             glGenVertexArrays(1, &mut vao as *mut _);
             glBindVertexArray(vao);
+
+            let features = query_features(is_gles2);
+            query::set_timer_query_supported(features.timer_query);
+
             GraphicsContext {
                 default_framebuffer,
-                shaders: vec![],
-                pipelines: vec![],
-                passes: vec![],
-                features: Features::from_gles2(is_gles2),
+                shaders: SlotMap::new("Shader"),
+                pipelines: SlotMap::new("Pipeline"),
+                passes: SlotMap::new("RenderPass"),
+                features,
                 cache: GlCache {
                     stored_index_buffer: 0,
                     stored_index_type: None,
                     stored_vertex_buffer: 0,
+                    stored_indirect_buffer: 0,
                     index_buffer: 0,
                     index_type: None,
                     vertex_buffer: 0,
+                    indirect_buffer: 0,
                     cur_pipeline: None,
+                    cur_pass: None,
                     color_blend: None,
                     alpha_blend: None,
                     stencil: None,
                     color_write: (true, true, true, true),
                     cull_face: CullFace::Nothing,
-                    stored_texture: 0,
-                    textures: [0; MAX_SHADERSTAGE_IMAGES],
+                    blend_color: (0., 0., 0., 0.),
+                    polygon_mode: PolygonMode::Fill,
+                    line_width: 1.,
+                    program_point_size: false,
+                    depth_clamp: false,
+                    alpha_to_coverage: false,
+                    stored_texture: (GL_TEXTURE_2D, 0),
+                    textures: [(GL_TEXTURE_2D, 0); MAX_SHADERSTAGE_IMAGES],
+                    samplers: [0; MAX_SHADERSTAGE_IMAGES],
                     attributes: [None; MAX_VERTEX_ATTRIBUTES],
                 },
+                uniform_stream: UniformStream {
+                    gl_buf: 0,
+                    offset: 0,
+                    alignment: 1,
+                },
+                program_cache: None,
+                emulated_samplers: HashMap::new(),
+                debug_callback: None,
+                debug_output: false,
+                resource_registry: None,
+                meta_validation: false,
                 display: None,
             }
         }
@@ -698,6 +2178,187 @@ impl GraphicsContext {
     pub fn features(&self) -> &Features {
         &self.features
     }
+
+    /// Whether a disjoint operation, like a GPU frequency change, happened since the last call.
+    /// Timer and timestamp results of queries in flight during the operation are garbage
+    /// and should be dropped. Always false without `features.timer_disjoint`.
+    pub fn gpu_disjoint(&mut self) -> bool {
+        if !self.features.timer_disjoint {
+            return false;
+        }
+        let mut disjoint = 0;
+        unsafe { glGetIntegerv(GL_GPU_DISJOINT_EXT, &mut disjoint as *mut _) };
+        disjoint != 0
+    }
+
+    /// Called by the platform backend right after `new`,
+    /// with `conf::Conf::program_cache_dir()`.
+    pub(crate) fn set_program_cache(&mut self, dir: Option<std::path::PathBuf>) {
+        self.program_cache = match dir {
+            Some(dir) if self.features.program_binary => unsafe { Some(ProgramCache::new(dir)) },
+            _ => None,
+        };
+    }
+
+    /// Called by the platform backend right after `new`, with `conf::Platform::gl_debug`.
+    pub(crate) fn set_debug_output(&mut self, enabled: bool) {
+        if !enabled || !self.features.debug_output {
+            return;
+        }
+        self.debug_output = true;
+        unsafe {
+            glEnable(GL_DEBUG_OUTPUT);
+            // messages are reported from the offending call, not from a driver thread
+            glEnable(GL_DEBUG_OUTPUT_SYNCHRONOUS);
+        }
+        let callback = self
+            .debug_callback
+            .get_or_insert_with(|| Box::new(debug::default_callback()));
+        unsafe { debug::install_callback(callback) };
+    }
+
+    /// Receive the driver's debug messages instead of the default logging of everything
+    /// but notifications. Only called with `conf::Platform::gl_debug` set and
+    /// `features.debug_output`.
+    ///
+    /// The callback runs inside the GL call reporting the message, so it should not make
+    /// GL calls itself.
+    pub fn set_debug_callback<F>(&mut self, callback: F)
+    where
+        F: FnMut(&DebugMessage) + 'static,
+    {
+        let mut callback: Box<debug::DebugCallback> = Box::new(Box::new(callback));
+        if self.debug_output {
+            unsafe { debug::install_callback(&mut callback) };
+        }
+        self.debug_callback = Some(callback);
+    }
+
+    /// Start a named group of commands, shown as a collapsible region by
+    /// frame debuggers like RenderDoc. Groups nest, every push needs a `pop_debug_group`.
+    /// Does nothing without `features.debug_output`.
+    pub fn push_debug_group(&mut self, name: &str) {
+        if self.features.debug_output {
+            unsafe { debug::push_group(name) };
+        }
+    }
+
+    pub fn pop_debug_group(&mut self) {
+        if self.features.debug_output {
+            unsafe { debug::pop_group() };
+        }
+    }
+
+    /// Name a GL object in driver messages and frame debuggers.
+    pub(crate) fn object_label(&self, identifier: GLenum, name: GLuint, label: &str) {
+        if self.features.debug_output {
+            unsafe { debug::object_label(identifier, name, label) };
+        }
+    }
+
+    /// Check the `ShaderMeta` of new shaders and the attributes of `Pipeline::try_with_params`
+    /// against the linked programs, failing with `ShaderError::MetaMismatch`.
+    ///
+    /// Off by default, as drivers drop uniforms unused by the shader and those are reported
+    /// as missing. `set_meta_validation(cfg!(debug_assertions))` limits it to debug builds.
+    pub fn set_meta_validation(&mut self, enabled: bool) {
+        self.meta_validation = enabled;
+    }
+
+    /// Keep shader sources, so `Shader` and `Pipeline` handles stay valid across a context loss,
+    /// see `EventHandler::context_restored_event`. With `keep_buffer_data` immutable buffers
+    /// keep a CPU copy of their contents and survive the loss too.
+    ///
+    /// Only resources created afterwards are registered, so this is best called first thing
+    /// in the `miniquad::start` closure. Textures, render passes and non-immutable buffers
+    /// are never restored.
+    pub fn enable_resource_registry(&mut self, keep_buffer_data: bool) {
+        self.resource_registry = Some(ResourceRegistry { keep_buffer_data });
+    }
+
+    /// Called by the platform backend once a new GL context replaced the lost one,
+    /// before `EventHandler::context_restored_event`.
+    ///
+    /// Starts over with a fresh state and recreates the registered resources. Handles of
+    /// everything else are left dangling.
+    #[cfg_attr(
+        not(any(target_os = "android", target_arch = "wasm32")),
+        allow(dead_code)
+    )]
+    pub(crate) fn restore(&mut self, is_gles2: bool) {
+        let mut restored = GraphicsContext::new(is_gles2);
+        mem::swap(&mut restored.shaders, &mut self.shaders);
+        mem::swap(&mut restored.pipelines, &mut self.pipelines);
+        mem::swap(&mut restored.passes, &mut self.passes);
+        restored.program_cache = self.program_cache.take();
+        restored.debug_callback = self.debug_callback.take();
+        restored.resource_registry = self.resource_registry.take();
+        restored.meta_validation = self.meta_validation;
+        restored.display = self.display;
+        let debug_output = self.debug_output;
+        *self = restored;
+        self.set_debug_output(debug_output);
+
+        // the sizes and CPU copies of `stream` and `dynamic` buffers died with the old context
+        registry::untrack_buffers();
+        unsafe { registry::restore_buffers() };
+
+        for shader in self.shaders.iter_mut() {
+            let (vertex_shader, fragment_shader) = match &shader.sources {
+                Some(sources) => sources.clone(),
+                None => continue,
+            };
+            let meta = if shader.reflected {
+                None
+            } else {
+                Some(shader.meta.clone())
+            };
+            let mut new_shader = match load_shader_internal(
+                &vertex_shader,
+                &fragment_shader,
+                meta,
+                self.features.uniform_buffers,
+                self.program_cache.as_ref(),
+                self.meta_validation,
+            ) {
+                Ok(new_shader) => new_shader,
+                Err(err) => {
+                    debug::log(
+                        debug::LogLevel::Error,
+                        "miniquad",
+                        &format!("Failed to restore a shader: {}", err),
+                    );
+                    continue;
+                }
+            };
+            new_shader.sources = shader.sources.take();
+            new_shader.label = shader.label.take();
+            if let (true, Some(label)) = (self.features.debug_output, &new_shader.label) {
+                unsafe { debug::object_label(GL_PROGRAM, new_shader.program, label) };
+            }
+            *shader = new_shader;
+        }
+
+        let shaders = &self.shaders;
+        for pipeline in self.pipelines.iter_mut() {
+            let shader = match shaders.get(pipeline.shader.0) {
+                Some(shader) if shader.sources.is_some() => shader,
+                _ => continue,
+            };
+            match vertex_layout(
+                shader.program,
+                &pipeline.buffer_layout,
+                &pipeline.attributes,
+            ) {
+                Ok(layout) => pipeline.layout = layout,
+                Err(err) => debug::log(
+                    debug::LogLevel::Error,
+                    "miniquad",
+                    &format!("Failed to restore a pipeline: {}", err),
+                ),
+            }
+        }
+    }
 }
 
 impl GraphicsContext {
@@ -706,6 +2367,9 @@ impl GraphicsContext {
 
         {
             let pipeline = &self.pipelines[pipeline.0];
+            if let (true, Some(label)) = (self.features.debug_output, &pipeline.label) {
+                unsafe { debug::insert_marker(label) };
+            }
             let shader = &mut self.shaders[pipeline.shader.0];
             unsafe {
                 glUseProgram(shader.program);
@@ -744,6 +2408,14 @@ impl GraphicsContext {
 
         self.set_stencil(self.pipelines[pipeline.0].params.stencil_test);
         self.set_color_write(self.pipelines[pipeline.0].params.color_write);
+
+        let params = self.pipelines[pipeline.0].params;
+        self.set_blend_color(params.blend_color);
+        self.set_polygon_mode(params.polygon_mode);
+        self.set_line_width(params.line_width);
+        self.set_program_point_size(params.program_point_size);
+        self.set_depth_clamp(params.depth_clamp);
+        self.set_alpha_to_coverage(params.alpha_to_coverage);
     }
 
     pub fn set_cull_face(&mut self, cull_face: CullFace) {
@@ -822,6 +2494,83 @@ impl GraphicsContext {
         self.cache.alpha_blend = alpha_blend;
     }
 
+    /// Color of the `BlendValue::ConstantColor` and `BlendValue::ConstantAlpha` factors.
+    pub fn set_blend_color(&mut self, blend_color: (f32, f32, f32, f32)) {
+        if self.cache.blend_color == blend_color {
+            return;
+        }
+        let (r, g, b, a) = blend_color;
+        unsafe { glBlendColor(r, g, b, a) }
+        self.cache.blend_color = blend_color;
+    }
+
+    /// Ignored without `features.polygon_mode`.
+    pub fn set_polygon_mode(&mut self, polygon_mode: PolygonMode) {
+        if self.cache.polygon_mode == polygon_mode || !self.features.polygon_mode {
+            return;
+        }
+        #[cfg(not(any(target_arch = "wasm32", target_os = "ios")))]
+        unsafe {
+            glPolygonMode(GL_FRONT_AND_BACK, polygon_mode.into());
+        }
+        self.cache.polygon_mode = polygon_mode;
+    }
+
+    /// Clamped to `features.line_width_range`.
+    pub fn set_line_width(&mut self, line_width: f32) {
+        let (min, max) = self.features.line_width_range;
+        let line_width = line_width.max(min).min(max);
+        if self.cache.line_width == line_width {
+            return;
+        }
+        unsafe { glLineWidth(line_width) }
+        self.cache.line_width = line_width;
+    }
+
+    /// Ignored on gles and webgl, where `gl_PointSize` is always used.
+    pub fn set_program_point_size(&mut self, program_point_size: bool) {
+        if self.cache.program_point_size == program_point_size || self.features.api != GlApi::Gl {
+            return;
+        }
+        unsafe {
+            if program_point_size {
+                glEnable(GL_PROGRAM_POINT_SIZE);
+            } else {
+                glDisable(GL_PROGRAM_POINT_SIZE);
+            }
+        }
+        self.cache.program_point_size = program_point_size;
+    }
+
+    /// Ignored without `features.depth_clamp`.
+    pub fn set_depth_clamp(&mut self, depth_clamp: bool) {
+        if self.cache.depth_clamp == depth_clamp || !self.features.depth_clamp {
+            return;
+        }
+        unsafe {
+            if depth_clamp {
+                glEnable(GL_DEPTH_CLAMP);
+            } else {
+                glDisable(GL_DEPTH_CLAMP);
+            }
+        }
+        self.cache.depth_clamp = depth_clamp;
+    }
+
+    pub fn set_alpha_to_coverage(&mut self, alpha_to_coverage: bool) {
+        if self.cache.alpha_to_coverage == alpha_to_coverage {
+            return;
+        }
+        unsafe {
+            if alpha_to_coverage {
+                glEnable(GL_SAMPLE_ALPHA_TO_COVERAGE);
+            } else {
+                glDisable(GL_SAMPLE_ALPHA_TO_COVERAGE);
+            }
+        }
+        self.cache.alpha_to_coverage = alpha_to_coverage;
+    }
+
     pub fn set_stencil(&mut self, stencil_test: Option<StencilState>) {
         if self.cache.stencil == stencil_test {
             return;
@@ -885,7 +2634,25 @@ impl GraphicsContext {
         }
     }
 
+    /// Write back the own state of a texture bound to `target` that had a sampler
+    /// emulated on it, before it is sampled or changed without one.
+    pub(crate) unsafe fn restore_texture_sampling(&mut self, target: GLenum, texture: GLuint) {
+        if let Some((own, _)) = self.emulated_samplers.remove(&texture) {
+            own.write(target, self.features.max_anisotropy);
+        }
+    }
+
     pub fn apply_bindings(&mut self, bindings: &Bindings) {
+        self.apply_bindings_with_samplers(bindings, &[]);
+    }
+
+    /// `apply_bindings` with samplers for `bindings.images`, matched by index.
+    /// Missing or None sampler means the texture's own filter and wrap settings.
+    pub fn apply_bindings_with_samplers(
+        &mut self,
+        bindings: &Bindings,
+        samplers: &[Option<Sampler>],
+    ) {
         let pip = &self.pipelines[self.cache.cur_pipeline.unwrap().0];
         let shader = &self.shaders[pip.shader.0];
 
@@ -895,8 +2662,21 @@ impl GraphicsContext {
                 .get(n)
                 .unwrap_or_else(|| panic!("Image count in bindings and shader did not match!"));
             if let Some(gl_loc) = shader_image.gl_loc {
+                let target = bindings_image.kind as GLenum;
+                let sampler = samplers.get(n).copied().flatten();
                 unsafe {
-                    self.cache.bind_texture(n, bindings_image.texture);
+                    self.cache.bind_texture(n, target, bindings_image.texture);
+                    if self.features.sampler_objects {
+                        self.cache.bind_sampler(n, sampler.map_or(0, |s| s.sampler));
+                    } else {
+                        emulate_sampler(
+                            &mut self.emulated_samplers,
+                            self.features.max_anisotropy,
+                            target,
+                            bindings_image.texture,
+                            sampler,
+                        );
+                    }
                     glUniform1i(gl_loc, n as i32);
                 }
             }
@@ -904,7 +2684,7 @@ impl GraphicsContext {
 
         self.cache.bind_buffer(
             GL_ELEMENT_ARRAY_BUFFER,
-            bindings.index_buffer.gl_buf,
+            bindings.index_buffer.gl_name(),
             bindings.index_buffer.index_type,
         );
 
@@ -917,22 +2697,16 @@ impl GraphicsContext {
 
             if let Some(Some(attribute)) = pip_attribute {
                 let vb = bindings.vertex_buffers[attribute.buffer_index];
+                let gl_vbuf = vb.gl_name();
 
                 if cached_attr.map_or(true, |cached_attr| {
-                    attribute != cached_attr.attribute || cached_attr.gl_vbuf != vb.gl_buf
+                    attribute != cached_attr.attribute || cached_attr.gl_vbuf != gl_vbuf
                 }) {
                     self.cache
-                        .bind_buffer(GL_ARRAY_BUFFER, vb.gl_buf, vb.index_type);
+                        .bind_buffer(GL_ARRAY_BUFFER, gl_vbuf, vb.index_type);
 
                     unsafe {
-                        glVertexAttribPointer(
-                            attr_index as GLuint,
-                            attribute.size,
-                            attribute.type_,
-                            GL_FALSE as u8,
-                            attribute.stride,
-                            attribute.offset as *mut _,
-                        );
+                        vertex_attrib_pointer(attr_index as GLuint, &attribute, attribute.offset);
                         if self.features.instancing {
                             glVertexAttribDivisor(attr_index as GLuint, attribute.divisor as u32);
                         }
@@ -940,10 +2714,7 @@ impl GraphicsContext {
                     };
 
                     let cached_attr = &mut self.cache.attributes[attr_index];
-                    *cached_attr = Some(CachedAttribute {
-                        attribute,
-                        gl_vbuf: vb.gl_buf,
-                    });
+                    *cached_attr = Some(CachedAttribute { attribute, gl_vbuf });
                 }
             } else {
                 if cached_attr.is_some() {
@@ -963,23 +2734,47 @@ impl GraphicsContext {
     #[doc(hidden)]
     /// Apply uniforms data from array of bytes with very special layout.
     /// Hidden because `apply_uniforms` is the recommended and safer way to work with uniforms.
+    // `uniform_ptr` always was read without the function being unsafe, changing that would break callers
+    #[allow(clippy::not_unsafe_ptr_arg_deref)]
     pub fn apply_uniforms_from_bytes(&mut self, uniform_ptr: *const u8, size: usize) {
         let pip = &self.pipelines[self.cache.cur_pipeline.unwrap().0];
         let shader = &self.shaders[pip.shader.0];
 
-        let mut offset = 0;
-
-        for (_, uniform) in shader.uniforms.iter().enumerate() {
-            use UniformType::*;
-
+        if let Some(block_size) = shader.uniform_block_size {
             assert!(
-                offset <= size - uniform.uniform_type.size() / 4,
-                "Uniforms struct does not match shader uniforms layout"
+                size == shader.uniforms_size || size == shader.uniforms_size.div_ceil(16) * 16,
+                "Uniforms struct does not match std140 uniform block layout"
             );
+            unsafe {
+                self.uniform_stream.push(uniform_ptr, size, block_size);
+            }
+            return;
+        }
+
+        assert!(
+            shader.uniforms_size <= size,
+            "Uniforms struct does not match shader uniforms layout"
+        );
+
+        // std140 arrays are padded to 16 bytes per element, while glUniform*v wants them packed
+        let mut packed = vec![];
+
+        for uniform in &shader.uniforms {
+            use UniformType::*;
 
             unsafe {
-                let data = (uniform_ptr as *const f32).offset(offset as isize);
-                let data_int = (uniform_ptr as *const i32).offset(offset as isize);
+                let mut data = uniform_ptr.add(uniform.offset);
+                let element_size = uniform.uniform_type.size();
+                if uniform.array_count > 1 && uniform.array_stride != element_size {
+                    packed.clear();
+                    for i in 0..uniform.array_count as usize {
+                        let element = data.add(i * uniform.array_stride);
+                        packed.extend_from_slice(std::slice::from_raw_parts(element, element_size));
+                    }
+                    data = packed.as_ptr();
+                }
+                let data_int = data as *const i32;
+                let data = data as *const f32;
 
                 if let Some(gl_loc) = uniform.gl_loc {
                     match uniform.uniform_type {
@@ -1013,7 +2808,6 @@ impl GraphicsContext {
                     }
                 }
             }
-            offset += uniform.uniform_type.size() / 4 * uniform.array_count as usize;
         }
     }
 
@@ -1059,7 +2853,8 @@ impl GraphicsContext {
 
     /// start rendering to an offscreen framebuffer
     pub fn begin_pass(&mut self, pass: impl Into<Option<RenderPass>>, action: PassAction) {
-        let (framebuffer, w, h) = match pass.into() {
+        let pass = pass.into();
+        let (framebuffer, w, h) = match pass {
             None => {
                 let (screen_width, screen_height) = self.screen_size();
                 (
@@ -1072,11 +2867,12 @@ impl GraphicsContext {
                 let pass = &self.passes[pass.0];
                 (
                     pass.gl_fb,
-                    pass.texture.width as i32,
-                    pass.texture.height as i32,
+                    pass.textures[0].width as i32,
+                    pass.textures[0].height as i32,
                 )
             }
         };
+        self.cache.cur_pass = pass;
         unsafe {
             glBindFramebuffer(GL_FRAMEBUFFER, framebuffer);
             glViewport(0, 0, w, h);
@@ -1091,10 +2887,89 @@ impl GraphicsContext {
             } => {
                 self.clear(color, depth, stencil);
             }
+            PassAction::ClearMrt {
+                colors,
+                depth,
+                stencil,
+            } => {
+                let attachments = pass.map_or(1, |pass| self.passes[pass.0].textures.len());
+                if attachments == 1 {
+                    self.clear(colors.first().copied().flatten(), depth, stencil);
+                } else {
+                    // glClear clears every enabled draw buffer, so each attachment
+                    // is cleared with only itself enabled
+                    let mut draw_buffers = vec![GL_NONE; attachments];
+                    for (n, color) in colors.iter().enumerate().take(attachments) {
+                        if let Some(color) = color {
+                            draw_buffers[n] = GL_COLOR_ATTACHMENT0 + n as GLenum;
+                            unsafe {
+                                glDrawBuffers(attachments as _, draw_buffers.as_ptr());
+                            }
+                            self.clear(Some(*color), None, None);
+                            draw_buffers[n] = GL_NONE;
+                        }
+                    }
+                    let draw_buffers = color_attachments(attachments);
+                    unsafe {
+                        glDrawBuffers(attachments as _, draw_buffers.as_ptr());
+                    }
+                    self.clear(None, depth, stencil);
+                }
+            }
         }
     }
 
+    /// Finish the current pass.
+    /// Multisampled render passes are resolved into their textures here,
+    /// and textures with mipmaps get their mip levels regenerated.
     pub fn end_render_pass(&mut self) {
+        if let Some(pass) = self.cache.cur_pass.take() {
+            let pass = &self.passes[pass.0];
+            let mipmapped: Vec<Texture> = pass
+                .textures
+                .iter()
+                .filter(|texture| texture.levels > 1)
+                .copied()
+                .collect();
+            if let Some(resolve_fb) = pass.resolve_fb {
+                let (w, h) = (
+                    pass.textures[0].width as i32,
+                    pass.textures[0].height as i32,
+                );
+                let attachments = pass.textures.len();
+                unsafe {
+                    glBindFramebuffer(GL_READ_FRAMEBUFFER, pass.gl_fb);
+                    glBindFramebuffer(GL_DRAW_FRAMEBUFFER, resolve_fb);
+                    // blit is affected by the scissor test
+                    glScissor(0, 0, w, h);
+
+                    // one blit resolves only one read buffer, so the attachments
+                    // are resolved one by one
+                    let mut draw_buffers = vec![GL_NONE; attachments];
+                    for n in 0..attachments {
+                        draw_buffers[n] = GL_COLOR_ATTACHMENT0 + n as GLenum;
+                        glReadBuffer(draw_buffers[n]);
+                        glDrawBuffers(attachments as _, draw_buffers.as_ptr());
+                        glBlitFramebuffer(0, 0, w, h, 0, 0, w, h, GL_COLOR_BUFFER_BIT, GL_NEAREST);
+                        draw_buffers[n] = GL_NONE;
+                    }
+                    if let Some(depth_texture) = pass.depth_texture {
+                        let mut mask = GL_DEPTH_BUFFER_BIT;
+                        if depth_texture.format == TextureFormat::Depth24Stencil8 {
+                            mask |= GL_STENCIL_BUFFER_BIT;
+                        }
+                        glBlitFramebuffer(0, 0, w, h, 0, 0, w, h, mask, GL_NEAREST);
+                    }
+
+                    glReadBuffer(GL_COLOR_ATTACHMENT0);
+                    let draw_buffers = color_attachments(attachments);
+                    glDrawBuffers(attachments as _, draw_buffers.as_ptr());
+                }
+            }
+            for texture in mipmapped {
+                texture.generate_mipmaps(self);
+            }
+        }
         unsafe {
             glBindFramebuffer(GL_FRAMEBUFFER, self.default_framebuffer);
             self.cache.bind_buffer(GL_ARRAY_BUFFER, 0, None);
@@ -1116,19 +2991,10 @@ impl GraphicsContext {
     /// NOTE: num_instances > 1 might be not supported by the GPU (gl2.1 and gles2).
     /// `features.instancing` check is required.
     pub fn draw(&self, base_element: i32, num_elements: i32, num_instances: i32) {
-        assert!(
-            self.cache.cur_pipeline.is_some(),
-            "Drawing without any binded pipeline"
-        );
-
-        if !self.features.instancing && num_instances != 1 {
-            println!("Instanced rendering is not supported by the GPU");
-            println!("Ignoring this draw call");
-            return;
-        }
-
-        let pip = &self.pipelines[self.cache.cur_pipeline.unwrap().0];
-        let primitive_type = pip.params.primitive_type.into();
+        let primitive_type = match self.draw_primitive_type(num_instances) {
+            Some(primitive_type) => primitive_type,
+            None => return,
+        };
         let index_type = self.cache.index_type.expect("Unset index buffer type");
 
         unsafe {
@@ -1150,65 +3016,375 @@ impl GraphicsContext {
             }
         }
     }
+
+    /// Draw vertices without the index buffer, using currently applied bindings and pipeline.
+    ///
+    /// + `first_vertex` specifies the first vertex of the vertex buffers to draw.
+    /// + `num_vertices` specifies how many vertices to draw.
+    /// + `num_instances` specifies how many instances should be rendered.
+    pub fn draw_arrays(&self, first_vertex: i32, num_vertices: i32, num_instances: i32) {
+        let primitive_type = match self.draw_primitive_type(num_instances) {
+            Some(primitive_type) => primitive_type,
+            None => return,
+        };
+
+        unsafe {
+            if self.features.instancing {
+                glDrawArraysInstanced(primitive_type, first_vertex, num_vertices, num_instances);
+            } else {
+                glDrawArrays(primitive_type, first_vertex, num_vertices);
+            }
+        }
+    }
+
+    /// Same as `draw`, but `base_vertex` is added to each index before fetching the vertex.
+    /// Lets many meshes share one big vertex buffer while keeping their indices zero-based.
+    ///
+    /// Emulated when `features.base_vertex` is false.
+    pub fn draw_base_vertex(
+        &mut self,
+        base_element: i32,
+        num_elements: i32,
+        base_vertex: i32,
+        num_instances: i32,
+    ) {
+        if base_vertex == 0 {
+            self.draw(base_element, num_elements, num_instances);
+            return;
+        }
+        if !self.features.base_vertex {
+            unsafe {
+                self.offset_vertex_attributes(base_vertex);
+            }
+            self.draw(base_element, num_elements, num_instances);
+            unsafe {
+                self.offset_vertex_attributes(0);
+            }
+            return;
+        }
+
+        #[cfg(not(target_os = "ios"))]
+        unsafe {
+            let primitive_type = match self.draw_primitive_type(num_instances) {
+                Some(primitive_type) => primitive_type,
+                None => return,
+            };
+            let index_type = self.cache.index_type.expect("Unset index buffer type");
+            let offset = (index_type.size() as i32 * base_element) as *mut _;
+
+            if self.features.instancing {
+                glDrawElementsInstancedBaseVertex(
+                    primitive_type,
+                    num_elements,
+                    index_type.into(),
+                    offset,
+                    num_instances,
+                    base_vertex,
+                );
+            } else {
+                glDrawElementsBaseVertex(
+                    primitive_type,
+                    num_elements,
+                    index_type.into(),
+                    offset,
+                    base_vertex,
+                );
+            }
+        }
+    }
+
+    /// Draw several ranges of the index buffer with one call, using currently applied
+    /// bindings and pipeline. There is no instancing for multi-draw, each range is drawn once.
+    ///
+    /// Falls back to a `draw_base_vertex` per range when `features.multi_draw` is false,
+    /// or when some range has a `base_vertex` and `features.base_vertex` is false.
+    pub fn draw_multi(&mut self, draws: &[DrawRange]) {
+        let with_base_vertex = draws.iter().any(|draw| draw.base_vertex != 0);
+        if !self.features.multi_draw || (with_base_vertex && !self.features.base_vertex) {
+            for draw in draws {
+                self.draw_base_vertex(draw.base_element, draw.num_elements, draw.base_vertex, 1);
+            }
+            return;
+        }
+
+        #[cfg(not(target_os = "ios"))]
+        unsafe {
+            let primitive_type = match self.draw_primitive_type(1) {
+                Some(primitive_type) => primitive_type,
+                None => return,
+            };
+            let index_type = self.cache.index_type.expect("Unset index buffer type");
+            let counts: Vec<GLsizei> = draws.iter().map(|draw| draw.num_elements).collect();
+            let offsets: Vec<*const _> = draws
+                .iter()
+                .map(|draw| (index_type.size() as i32 * draw.base_element) as *const _)
+                .collect();
+
+            if with_base_vertex {
+                let base_vertices: Vec<GLint> = draws.iter().map(|draw| draw.base_vertex).collect();
+                glMultiDrawElementsBaseVertex(
+                    primitive_type,
+                    counts.as_ptr(),
+                    index_type.into(),
+                    offsets.as_ptr(),
+                    draws.len() as _,
+                    base_vertices.as_ptr(),
+                );
+            } else {
+                glMultiDrawElements(
+                    primitive_type,
+                    counts.as_ptr(),
+                    index_type.into(),
+                    offsets.as_ptr(),
+                    draws.len() as _,
+                );
+            }
+        }
+    }
+
+    /// Draw `count` `DrawIndirectCommand`s read from `buffer`, starting `offset` bytes into it,
+    /// using currently applied bindings and pipeline. The commands may be written by the GPU
+    /// itself, a compute shader or transform feedback, without a round trip through the CPU.
+    ///
+    /// NOTE: requires `features.draw_indirect`, the draw call is ignored otherwise.
+    pub fn draw_indirect(&mut self, buffer: &Buffer, offset: usize, count: usize) {
+        let stride = mem::size_of::<DrawIndirectCommand>();
+        assert!(
+            buffer.buffer_type == BufferType::IndirectBuffer,
+            "draw_indirect needs a BufferType::IndirectBuffer"
+        );
+        assert!(
+            offset & 3 == 0,
+            "Indirect buffer offset should be 4 bytes aligned"
+        );
+        assert!(offset + count * stride <= buffer.size());
+
+        if !self.features.draw_indirect {
+            println!("Indirect drawing is not supported by the GPU");
+            println!("Ignoring this draw call");
+            return;
+        }
+
+        #[cfg(not(target_os = "ios"))]
+        unsafe {
+            let primitive_type = match self.draw_primitive_type(1) {
+                Some(primitive_type) => primitive_type,
+                None => return,
+            };
+            let index_type = self.cache.index_type.expect("Unset index buffer type");
+            self.cache
+                .bind_buffer(GL_DRAW_INDIRECT_BUFFER, buffer.gl_name(), None);
+
+            #[cfg(not(target_os = "macos"))]
+            if self.features.multi_draw_indirect {
+                glMultiDrawElementsIndirect(
+                    primitive_type,
+                    index_type.into(),
+                    offset as *const _,
+                    count as _,
+                    stride as _,
+                );
+                return;
+            }
+
+            for n in 0..count {
+                glDrawElementsIndirect(
+                    primitive_type,
+                    index_type.into(),
+                    (offset + n * stride) as *const _,
+                );
+            }
+        }
+    }
+
+    /// Primitive type of the applied pipeline, None if the draw call should be skipped.
+    fn draw_primitive_type(&self, num_instances: i32) -> Option<GLenum> {
+        assert!(
+            self.cache.cur_pipeline.is_some(),
+            "Drawing without any binded pipeline"
+        );
+
+        if !self.features.instancing && num_instances != 1 {
+            println!("Instanced rendering is not supported by the GPU");
+            println!("Ignoring this draw call");
+            return None;
+        }
+
+        let pip = &self.pipelines[self.cache.cur_pipeline.unwrap().0];
+        Some(pip.params.primitive_type.into())
+    }
+
+    /// Moves per-vertex attribute pointers `base_vertex` vertices forward,
+    /// emulating base vertex draws. `offset_vertex_attributes(0)` restores them.
+    unsafe fn offset_vertex_attributes(&mut self, base_vertex: i32) {
+        for attr_index in 0..MAX_VERTEX_ATTRIBUTES {
+            let cached_attr = match self.cache.attributes[attr_index] {
+                Some(cached_attr) if cached_attr.attribute.divisor == 0 => cached_attr,
+                _ => continue,
+            };
+            let attribute = cached_attr.attribute;
+            self.cache
+                .bind_buffer(GL_ARRAY_BUFFER, cached_attr.gl_vbuf, None);
+            vertex_attrib_pointer(
+                attr_index as GLuint,
+                &attribute,
+                attribute.offset + base_vertex as i64 * attribute.stride as i64,
+            );
+        }
+    }
+}
+
+/// Points the attribute at `offset` bytes into the bound GL_ARRAY_BUFFER.
+unsafe fn vertex_attrib_pointer(
+    attr_index: GLuint,
+    attribute: &VertexAttributeInternal,
+    offset: i64,
+) {
+    if attribute.integer {
+        glVertexAttribIPointer(
+            attr_index,
+            attribute.size,
+            attribute.type_,
+            attribute.stride,
+            offset as *mut _,
+        );
+    } else {
+        glVertexAttribPointer(
+            attr_index,
+            attribute.size,
+            attribute.type_,
+            attribute.normalized as u8,
+            attribute.stride,
+            offset as *mut _,
+        );
+    }
+}
+
+/// Compiles and links the program, keeping its binary in the `program_cache`.
+unsafe fn link_program(
+    vertex_shader_source: &str,
+    fragment_shader_source: &str,
+    program_cache: Option<&ProgramCache>,
+) -> Result<GLuint, ShaderError> {
+    let vertex_shader = load_shader(GL_VERTEX_SHADER, vertex_shader_source)?;
+    let fragment_shader = load_shader(GL_FRAGMENT_SHADER, fragment_shader_source)?;
+
+    let program = glCreateProgram();
+    glAttachShader(program, vertex_shader);
+    glAttachShader(program, fragment_shader);
+    if let Some(cache) = program_cache {
+        cache.prepare(program);
+    }
+    glLinkProgram(program);
+
+    let mut link_status = 0;
+    glGetProgramiv(program, GL_LINK_STATUS, &mut link_status as *mut _);
+    if link_status == 0 {
+        let mut max_length: i32 = 0;
+        glGetProgramiv(program, GL_INFO_LOG_LENGTH, &mut max_length as *mut _);
+
+        let mut error_message = vec![0u8; max_length as usize + 1];
+        glGetProgramInfoLog(
+            program,
+            max_length,
+            &mut max_length as *mut _,
+            error_message.as_mut_ptr() as *mut _,
+        );
+        assert!(max_length >= 1);
+        let error_message =
+            std::string::String::from_utf8_lossy(&error_message[0..max_length as usize - 1]);
+        return Err(ShaderError::LinkError(error_message.to_string()));
+    }
+
+    if let Some(cache) = program_cache {
+        cache.store(vertex_shader_source, fragment_shader_source, program);
+    }
+
+    Ok(program)
 }
 
 fn load_shader_internal(
     vertex_shader: &str,
     fragment_shader: &str,
-    meta: ShaderMeta,
+    meta: Option<ShaderMeta>,
+    uniform_buffers: bool,
+    program_cache: Option<&ProgramCache>,
+    validate: bool,
 ) -> Result<ShaderInternal, ShaderError> {
-    unsafe {
-        let vertex_shader = load_shader(GL_VERTEX_SHADER, vertex_shader)?;
-        let fragment_shader = load_shader(GL_FRAGMENT_SHADER, fragment_shader)?;
+    let reflected = meta.is_none();
 
-        let program = glCreateProgram();
-        glAttachShader(program, vertex_shader);
-        glAttachShader(program, fragment_shader);
-        glLinkProgram(program);
-
-        let mut link_status = 0;
-        glGetProgramiv(program, GL_LINK_STATUS, &mut link_status as *mut _);
-        if link_status == 0 {
-            let mut max_length: i32 = 0;
-            glGetProgramiv(program, GL_INFO_LOG_LENGTH, &mut max_length as *mut _);
-
-            let mut error_message = vec![0u8; max_length as usize + 1];
-            glGetProgramInfoLog(
-                program,
-                max_length,
-                &mut max_length as *mut _,
-                error_message.as_mut_ptr() as *mut _,
-            );
-            assert!(max_length >= 1);
-            let error_message =
-                std::string::String::from_utf8_lossy(&error_message[0..max_length as usize - 1]);
-            return Err(ShaderError::LinkError(error_message.to_string()));
-        }
+    unsafe {
+        let cached = program_cache.and_then(|cache| cache.load(vertex_shader, fragment_shader));
+        let program = match cached {
+            Some(program) => program,
+            None => link_program(vertex_shader, fragment_shader, program_cache)?,
+        };
 
         glUseProgram(program);
 
+        let active_uniforms = active_variables(program, true);
+        let meta = match meta {
+            Some(meta) => {
+                if validate {
+                    let errors = validate_meta(&meta, &active_uniforms);
+                    if !errors.is_empty() {
+                        glDeleteProgram(program);
+                        return Err(ShaderError::MetaMismatch(errors.join("\n")));
+                    }
+                }
+                meta
+            }
+            None => reflect_meta(program, &active_uniforms)?,
+        };
+
         #[rustfmt::skip]
         let images = meta.images.iter().map(|name| ShaderImage {
             gl_loc: get_uniform_location(program, name),
         }).collect();
 
+        let (offsets, uniforms_size) = meta.uniforms.offsets();
+
         #[rustfmt::skip]
-        let uniforms = meta.uniforms.uniforms.iter().scan(0, |offset, uniform| {
-            let res = ShaderUniform {
+        let uniforms = meta.uniforms.uniforms.iter().zip(offsets).map(|(uniform, (offset, array_stride))| {
+            ShaderUniform {
                 gl_loc: get_uniform_location(program, &uniform.name),
-                _offset: *offset,
+                offset,
+                array_stride,
                 _size: uniform.uniform_type.size(),
                 uniform_type: uniform.uniform_type,
                 array_count: uniform.array_count as _,
-            };
-            *offset += uniform.uniform_type.size() * uniform.array_count;
-            Some(res)
+            }
         }).collect();
 
+        let mut uniform_block_size = None;
+        if let (true, Some(block)) = (uniform_buffers, &meta.uniforms.std140_block) {
+            let cname = CString::new(block.as_str())?;
+            let index = glGetUniformBlockIndex(program, cname.as_ptr());
+
+            // shader with plain uniforms goes through glUniform* path
+            if index != GL_INVALID_INDEX {
+                let mut size = 0;
+                glGetActiveUniformBlockiv(
+                    program,
+                    index,
+                    GL_UNIFORM_BLOCK_DATA_SIZE,
+                    &mut size as *mut _,
+                );
+                glUniformBlockBinding(program, index, UNIFORM_BLOCK_BINDING);
+                uniform_block_size = Some(size as usize);
+            }
+        }
+
         Ok(ShaderInternal {
             program,
+            meta,
+            reflected,
             images,
             uniforms,
+            uniforms_size,
+            uniform_block_size,
+            label: None,
+            sources: None,
         })
     }
 }
@@ -1269,6 +3445,26 @@ pub enum CullFace {
     Back,
 }
 
+/// How polygons are rasterized, see `Features::polygon_mode`.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum PolygonMode {
+    Fill,
+    /// Wireframe, edges are drawn as lines of `PipelineParams::line_width`.
+    Line,
+    /// Only the vertices are drawn, see `PipelineParams::program_point_size`.
+    Point,
+}
+
+impl From<PolygonMode> for GLenum {
+    fn from(polygon_mode: PolygonMode) -> Self {
+        match polygon_mode {
+            PolygonMode::Fill => GL_FILL,
+            PolygonMode::Line => GL_LINE,
+            PolygonMode::Point => GL_POINT,
+        }
+    }
+}
+
 /// Define front- and back-facing polygons.
 #[derive(Debug, PartialEq, Eq, Clone, Copy)]
 pub enum FrontFaceOrder {
@@ -1326,6 +3522,10 @@ pub enum BlendValue {
     SourceAlpha,
     DestinationColor,
     DestinationAlpha,
+    /// `PipelineParams::blend_color`. WebGL does not allow a constant color
+    /// and a constant alpha factor in the same blend function.
+    ConstantColor,
+    ConstantAlpha,
 }
 
 /// Blend factors.
@@ -1363,10 +3563,14 @@ impl From<BlendFactor> for GLenum {
             BlendFactor::Value(BlendValue::SourceAlpha) => GL_SRC_ALPHA,
             BlendFactor::Value(BlendValue::DestinationColor) => GL_DST_COLOR,
             BlendFactor::Value(BlendValue::DestinationAlpha) => GL_DST_ALPHA,
+            BlendFactor::Value(BlendValue::ConstantColor) => GL_CONSTANT_COLOR,
+            BlendFactor::Value(BlendValue::ConstantAlpha) => GL_CONSTANT_ALPHA,
             BlendFactor::OneMinusValue(BlendValue::SourceColor) => GL_ONE_MINUS_SRC_COLOR,
             BlendFactor::OneMinusValue(BlendValue::SourceAlpha) => GL_ONE_MINUS_SRC_ALPHA,
             BlendFactor::OneMinusValue(BlendValue::DestinationColor) => GL_ONE_MINUS_DST_COLOR,
             BlendFactor::OneMinusValue(BlendValue::DestinationAlpha) => GL_ONE_MINUS_DST_ALPHA,
+            BlendFactor::OneMinusValue(BlendValue::ConstantColor) => GL_ONE_MINUS_CONSTANT_COLOR,
+            BlendFactor::OneMinusValue(BlendValue::ConstantAlpha) => GL_ONE_MINUS_CONSTANT_ALPHA,
             BlendFactor::SourceAlphaSaturate => GL_SRC_ALPHA_SATURATE,
         }
     }
@@ -1503,10 +3707,25 @@ pub struct PipelineParams {
     pub stencil_test: Option<StencilState>,
     pub color_write: ColorMask,
     pub primitive_type: PrimitiveType,
+    /// Color of the `BlendValue::ConstantColor` and `BlendValue::ConstantAlpha` factors.
+    pub blend_color: (f32, f32, f32, f32),
+    /// Wireframe and point rendering, ignored without `Features::polygon_mode`.
+    pub polygon_mode: PolygonMode,
+    /// Width of lines in pixels, clamped to `Features::line_width_range`.
+    pub line_width: f32,
+    /// Point size is taken from `gl_PointSize` of the vertex shader instead of being 1.0.
+    /// Only matters on desktop gl, gles and webgl always use `gl_PointSize`.
+    pub program_point_size: bool,
+    /// Clamp depth instead of clipping primitives at the near and far planes,
+    /// ignored without `Features::depth_clamp`.
+    pub depth_clamp: bool,
+    /// Fragment alpha controls the multisample coverage, for antialiased
+    /// alpha tested foliage and the like. Only affects MSAA render passes.
+    pub alpha_to_coverage: bool,
 }
 
-#[derive(Copy, Clone, Debug)]
-pub struct Pipeline(usize);
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Pipeline(SlotId);
 
 impl Default for PipelineParams {
     fn default() -> PipelineParams {
@@ -1521,6 +3740,12 @@ impl Default for PipelineParams {
             stencil_test: None,
             color_write: (true, true, true, true),
             primitive_type: PrimitiveType::Triangles,
+            blend_color: (0., 0., 0., 0.),
+            polygon_mode: PolygonMode::Fill,
+            line_width: 1.,
+            program_point_size: false,
+            depth_clamp: false,
+            alpha_to_coverage: false,
         }
     }
 }
@@ -1535,120 +3760,263 @@ impl Pipeline {
         Self::with_params(ctx, buffer_layout, attributes, shader, Default::default())
     }
 
-    pub fn with_params(
+    pub fn try_new(
+        ctx: &mut Context,
+        buffer_layout: &[BufferLayout],
+        attributes: &[VertexAttribute],
+        shader: Shader,
+    ) -> Result<Pipeline, ShaderError> {
+        Self::try_with_params(ctx, buffer_layout, attributes, shader, Default::default())
+    }
+
+    /// Same as `with_params`, but with `GraphicsContext::set_meta_validation` fails with `ShaderError::MetaMismatch`
+    /// when an attribute is not found in the shader or its type does not fit the format:
+    /// matrices need `Mat4`, `int`/`uint` vectors need one of `VertexFormat::is_integer`.
+    /// `with_params` just leaves such attributes unbound.
+    ///
+    /// Fails with `ShaderError::VertexStrideTooLarge`, and with `ShaderError::MetaMismatch`
+    /// for a missing buffer layout or an `int`/`uint` attribute without an integer format,
+    /// where `with_params` panics.
+    pub fn try_with_params(
         ctx: &mut Context,
         buffer_layout: &[BufferLayout],
         attributes: &[VertexAttribute],
         shader: Shader,
         params: PipelineParams,
-    ) -> Pipeline {
-        #[derive(Clone, Copy, Default)]
-        struct BufferCacheData {
-            stride: i32,
-            offset: i64,
+    ) -> Result<Pipeline, ShaderError> {
+        if ctx.meta_validation {
+            let program = ctx.shaders[shader.0].program;
+            let errors = unsafe { validate_attributes(program, attributes) };
+            if !errors.is_empty() {
+                return Err(ShaderError::MetaMismatch(errors.join("\n")));
+            }
         }
 
-        let mut buffer_cache: Vec<BufferCacheData> =
-            vec![BufferCacheData::default(); buffer_layout.len()];
+        Self::create(ctx, buffer_layout, attributes, shader, params)
+    }
 
-        for VertexAttribute {
-            format,
-            buffer_index,
-            ..
-        } in attributes
-        {
-            let layout = buffer_layout.get(*buffer_index).unwrap_or_else(|| panic!());
-            let mut cache = buffer_cache
-                .get_mut(*buffer_index)
-                .unwrap_or_else(|| panic!());
+    pub fn with_params(
+        ctx: &mut Context,
+        buffer_layout: &[BufferLayout],
+        attributes: &[VertexAttribute],
+        shader: Shader,
+        params: PipelineParams,
+    ) -> Pipeline {
+        Self::create(ctx, buffer_layout, attributes, shader, params)
+            .unwrap_or_else(|err| panic!("{}", err))
+    }
 
-            if layout.stride == 0 {
-                cache.stride += format.byte_len();
-            } else {
-                cache.stride = layout.stride;
-            }
-            // WebGL 1 limitation
-            assert!(cache.stride <= 255);
-        }
+    fn create(
+        ctx: &mut Context,
+        buffer_layout: &[BufferLayout],
+        attributes: &[VertexAttribute],
+        shader: Shader,
+        params: PipelineParams,
+    ) -> Result<Pipeline, ShaderError> {
+        validate_strides(
+            buffer_layout,
+            attributes,
+            ctx.features.max_vertex_attrib_stride,
+        )?;
 
         let program = ctx.shaders[shader.0].program;
+        let vertex_layout =
+            vertex_layout(program, buffer_layout, attributes).map_err(ShaderError::MetaMismatch)?;
 
-        let attributes_len = attributes
-            .iter()
-            .map(|layout| match layout.format {
-                VertexFormat::Mat4 => 4,
-                _ => 1,
-            })
-            .sum();
+        let pipeline = PipelineInternal {
+            layout: vertex_layout,
+            buffer_layout: buffer_layout.to_vec(),
+            attributes: attributes.to_vec(),
+            shader,
+            params,
+            label: None,
+        };
 
-        let mut vertex_layout: Vec<Option<VertexAttributeInternal>> = vec![None; attributes_len];
+        Ok(Pipeline(ctx.pipelines.insert(pipeline)))
+    }
 
-        for VertexAttribute {
-            name,
-            format,
-            buffer_index,
-        } in attributes
-        {
-            let mut buffer_data = &mut buffer_cache
-                .get_mut(*buffer_index)
-                .unwrap_or_else(|| panic!());
-            let layout = buffer_layout.get(*buffer_index).unwrap_or_else(|| panic!());
-
-            let cname = CString::new(*name).unwrap_or_else(|e| panic!("{}", e));
-            let attr_loc = unsafe { glGetAttribLocation(program, cname.as_ptr() as *const _) };
-            let attr_loc = if attr_loc == -1 { None } else { Some(attr_loc) };
-            let divisor = if layout.step_func == VertexStep::PerVertex {
-                0
-            } else {
-                layout.step_rate
-            };
+    pub fn set_blend(&self, ctx: &mut Context, color_blend: Option<BlendState>) {
+        let mut pipeline = &mut ctx.pipelines[self.0];
+        pipeline.params.color_blend = color_blend;
+    }
 
-            let mut attributes_count: usize = 1;
-            let mut format = *format;
+    /// GL has no pipeline objects to name, so `apply_pipeline` of a labelled pipeline
+    /// inserts a `DebugType::Marker` message with the label instead, shown inline by
+    /// frame debuggers. Does nothing without `features.debug_output`.
+    pub fn label(&self, ctx: &mut Context, label: &str) {
+        ctx.pipelines[self.0].label = Some(label.to_string());
+    }
 
-            if format == VertexFormat::Mat4 {
-                format = VertexFormat::Float4;
-                attributes_count = 4;
-            }
-            for i in 0..attributes_count {
-                if let Some(attr_loc) = attr_loc {
-                    let attr_loc = attr_loc as GLuint + i as GLuint;
+    /// Free the handle. The shader is not deleted, it may be shared with other pipelines.
+    /// Using the pipeline afterwards panics.
+    pub fn delete(&self, ctx: &mut Context) {
+        ctx.pipelines
+            .remove(self.0)
+            .expect("Pipeline used after delete");
+        if ctx.cache.cur_pipeline == Some(*self) {
+            ctx.cache.cur_pipeline = None;
+        }
+    }
+}
 
-                    let attr = VertexAttributeInternal {
-                        attr_loc,
-                        size: format.size(),
-                        type_: format.type_(),
-                        offset: buffer_data.offset,
-                        stride: buffer_data.stride,
-                        buffer_index: *buffer_index,
-                        divisor,
-                    };
+/// Attributes indexed by their location in the program. Fails when an attribute
+/// refers to a missing buffer layout or the program puts it past the attributes count.
+fn vertex_layout(
+    program: GLuint,
+    buffer_layout: &[BufferLayout],
+    attributes: &[VertexAttribute],
+) -> Result<Vec<Option<VertexAttributeInternal>>, String> {
+    let strides = buffer_strides(buffer_layout, attributes);
+    let mut buffer_offsets = vec![0i64; buffer_layout.len()];
+
+    let attributes_len = attributes
+        .iter()
+        .map(|layout| match layout.format {
+            VertexFormat::Mat4 => 4,
+            _ => 1,
+        })
+        .sum();
+
+    let mut vertex_layout: Vec<Option<VertexAttributeInternal>> = vec![None; attributes_len];
+    let active_attributes = unsafe { active_variables(program, false) };
+
+    for VertexAttribute {
+        name,
+        format,
+        buffer_index,
+        offset,
+    } in attributes
+    {
+        let layout = buffer_layout
+            .get(*buffer_index)
+            .ok_or_else(|| missing_buffer(name, *buffer_index, buffer_layout.len()))?;
+        let buffer_offset = &mut buffer_offsets[*buffer_index];
+        if let Some(offset) = offset {
+            *buffer_offset = *offset as i64;
+        }
 
-                    assert!(
-                        attr_loc < vertex_layout.len() as u32,
-                        "attribute: {} outside of allocated attributes array len: {}",
+        let cname = CString::new(*name).unwrap_or_else(|e| panic!("{}", e));
+        let attr_loc = unsafe { glGetAttribLocation(program, cname.as_ptr() as *const _) };
+        let attr_loc = if attr_loc == -1 { None } else { Some(attr_loc) };
+        // integer attributes of gl3 and gles3 shaders, ints are converted to floats otherwise
+        let integer_input = active_attributes
+            .iter()
+            .any(|active| active.name == *name && is_integer_attribute(active.gl_type));
+        if integer_input && !format.is_integer() {
+            return Err(format!(
+                "attribute \"{}\" is {:?} in the layout, but an integer in the program",
+                name, format
+            ));
+        }
+        let integer = integer_input && format.is_integer();
+        let divisor = if layout.step_func == VertexStep::PerVertex {
+            0
+        } else {
+            layout.step_rate
+        };
+
+        let mut attributes_count: usize = 1;
+        let mut format = *format;
+
+        if format == VertexFormat::Mat4 {
+            format = VertexFormat::Float4;
+            attributes_count = 4;
+        }
+        for i in 0..attributes_count {
+            if let Some(attr_loc) = attr_loc {
+                let attr_loc = attr_loc as GLuint + i as GLuint;
+
+                let attr = VertexAttributeInternal {
+                    attr_loc,
+                    size: format.size(),
+                    type_: format.type_(),
+                    normalized: format.normalized(),
+                    integer,
+                    offset: *buffer_offset,
+                    stride: strides[*buffer_index],
+                    buffer_index: *buffer_index,
+                    divisor,
+                };
+
+                if attr_loc >= vertex_layout.len() as u32 {
+                    return Err(format!(
+                        "attribute \"{}\" is at location {}, outside of the {} attributes of the pipeline",
                         name,
+                        attr_loc,
                         vertex_layout.len()
-                    );
-                    vertex_layout[attr_loc as usize] = Some(attr);
+                    ));
                 }
-                buffer_data.offset += format.byte_len() as i64
+                vertex_layout[attr_loc as usize] = Some(attr);
             }
+            *buffer_offset += format.byte_len() as i64
         }
+    }
 
-        let pipeline = PipelineInternal {
-            layout: vertex_layout,
-            shader,
-            params,
-        };
+    Ok(vertex_layout)
+}
 
-        ctx.pipelines.push(pipeline);
-        Pipeline(ctx.pipelines.len() - 1)
+fn missing_buffer(name: &str, buffer_index: usize, buffers: usize) -> String {
+    format!(
+        "attribute \"{}\" uses buffer {}, but there are only {} buffer layouts",
+        name, buffer_index, buffers
+    )
+}
+
+/// Byte stride of each buffer. Zero `BufferLayout::stride` is the end of the last attribute
+/// in the buffer, attributes without an explicit offset follow the previous one.
+/// Attributes of missing buffers are skipped.
+fn buffer_strides(buffer_layout: &[BufferLayout], attributes: &[VertexAttribute]) -> Vec<i32> {
+    let mut ends = vec![0; buffer_layout.len()];
+    let mut strides = vec![0; buffer_layout.len()];
+
+    for attribute in attributes {
+        if attribute.buffer_index >= buffer_layout.len() {
+            continue;
+        }
+        let end = &mut ends[attribute.buffer_index];
+        *end = attribute.offset.unwrap_or(*end) + attribute.format.byte_len();
+        strides[attribute.buffer_index] = strides[attribute.buffer_index].max(*end);
     }
 
-    pub fn set_blend(&self, ctx: &mut Context, color_blend: Option<BlendState>) {
-        let mut pipeline = &mut ctx.pipelines[self.0];
-        pipeline.params.color_blend = color_blend;
+    buffer_layout
+        .iter()
+        .zip(strides)
+        .map(|(layout, stride)| {
+            if layout.stride == 0 {
+                stride
+            } else {
+                layout.stride
+            }
+        })
+        .collect()
+}
+
+/// `ShaderError::MetaMismatch` for the first attribute of a missing buffer,
+/// `ShaderError::VertexStrideTooLarge` for the first buffer over `max_stride`.
+fn validate_strides(
+    buffer_layout: &[BufferLayout],
+    attributes: &[VertexAttribute],
+    max_stride: i32,
+) -> Result<(), ShaderError> {
+    if let Some(attribute) = attributes
+        .iter()
+        .find(|attribute| attribute.buffer_index >= buffer_layout.len())
+    {
+        return Err(ShaderError::MetaMismatch(missing_buffer(
+            attribute.name,
+            attribute.buffer_index,
+            buffer_layout.len(),
+        )));
+    }
+
+    let strides = buffer_strides(buffer_layout, attributes);
+    match strides.iter().position(|stride| *stride > max_stride) {
+        Some(buffer_index) => Err(ShaderError::VertexStrideTooLarge {
+            buffer_index,
+            stride: strides[buffer_index],
+            max_stride,
+        }),
+        None => Ok(()),
     }
 }
 
@@ -1657,6 +4025,8 @@ struct VertexAttributeInternal {
     attr_loc: GLuint,
     size: i32,
     type_: GLuint,
+    normalized: bool,
+    integer: bool,
     offset: i64,
     stride: i32,
     buffer_index: usize,
@@ -1665,8 +4035,34 @@ struct VertexAttributeInternal {
 
 struct PipelineInternal {
     layout: Vec<Option<VertexAttributeInternal>>,
+    /// `layout` source, kept to query attribute locations again on `Shader::reload`.
+    buffer_layout: Vec<BufferLayout>,
+    attributes: Vec<VertexAttribute>,
     shader: Shader,
     params: PipelineParams,
+    /// Set by `Pipeline::label`, inserted as a marker message on `apply_pipeline`.
+    label: Option<String>,
+}
+
+/// Range of the index buffer for `GraphicsContext::draw_multi`,
+/// the same as `draw_base_vertex` arguments.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct DrawRange {
+    pub base_element: i32,
+    pub num_elements: i32,
+    pub base_vertex: i32,
+}
+
+/// Layout of the commands in a `BufferType::IndirectBuffer`, as expected by glDrawElementsIndirect.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct DrawIndirectCommand {
+    pub num_elements: u32,
+    pub num_instances: u32,
+    pub base_element: u32,
+    pub base_vertex: i32,
+    /// Should be 0 before gl4.2 and on gles.
+    pub base_instance: u32,
 }
 
 /// Geometry bindings
@@ -1684,7 +4080,8 @@ pub struct Bindings {
     /// triangle.
     pub index_buffer: Buffer,
     /// Textures to be used with when drawing the geometry in the fragment
-    /// shader.
+    /// shader. Sampled with their own filter and wrap settings, unless
+    /// `GraphicsContext::apply_bindings_with_samplers` gives them a `Sampler`.
     pub images: Vec<Texture>,
 }
 
@@ -1692,6 +4089,9 @@ pub struct Bindings {
 pub enum BufferType {
     VertexBuffer,
     IndexBuffer,
+    /// `DrawIndirectCommand`s for `GraphicsContext::draw_indirect`.
+    /// Requires `features.draw_indirect`.
+    IndirectBuffer,
 }
 
 #[derive(Clone, Copy, Debug, PartialEq)]
@@ -1705,6 +4105,7 @@ fn gl_buffer_target(buffer_type: &BufferType) -> GLenum {
     match buffer_type {
         BufferType::VertexBuffer => GL_ARRAY_BUFFER,
         BufferType::IndexBuffer => GL_ELEMENT_ARRAY_BUFFER,
+        BufferType::IndirectBuffer => GL_DRAW_INDIRECT_BUFFER,
     }
 }
 
@@ -1720,8 +4121,11 @@ fn gl_usage(usage: &Usage) -> GLenum {
 pub struct Buffer {
     gl_buf: GLuint,
     buffer_type: BufferType,
+    usage: Usage,
     size: usize,
     index_type: Option<IndexType>,
+    /// Id in the resource registry, 0 for unregistered buffers.
+    registry_id: u32,
 }
 
 impl Buffer {
@@ -1760,15 +4164,31 @@ impl Buffer {
             glBufferSubData(gl_target, 0, size as _, data.as_ptr() as *const _);
             ctx.cache.restore_buffer_binding(gl_target);
         }
+        // GL names are reused, the size and shadow may be left from a deleted buffer
+        registry::untrack_buffer(gl_buf);
+
+        let registry_id = match &ctx.resource_registry {
+            Some(registry) if registry.keep_buffer_data => {
+                let bytes = unsafe { std::slice::from_raw_parts(data.as_ptr() as *const u8, size) };
+                registry::register_buffer(gl_buf, buffer_type, bytes.to_vec())
+            }
+            _ => 0,
+        };
 
         Buffer {
             gl_buf,
             buffer_type,
+            usage: Usage::Immutable,
             size,
             index_type,
+            registry_id,
         }
     }
 
+    /// Buffer rewritten every frame or so. Updating it from offset 0 orphans the old storage,
+    /// see `update_range`.
+    ///
+    /// Index buffers are of `IndexType::Short`, `index_stream` allows other index types.
     pub fn stream(ctx: &mut Context, buffer_type: BufferType, size: usize) -> Buffer {
         let index_type = if buffer_type == BufferType::IndexBuffer {
             Some(IndexType::Short)
@@ -1776,29 +4196,44 @@ impl Buffer {
             None
         };
 
-        let gl_target = gl_buffer_target(&buffer_type);
-        let gl_usage = gl_usage(&Usage::Stream);
-        let mut gl_buf: u32 = 0;
-
-        unsafe {
-            glGenBuffers(1, &mut gl_buf as *mut _);
-            ctx.cache.store_buffer_binding(gl_target);
-            ctx.cache.bind_buffer(gl_target, gl_buf, None);
-            glBufferData(gl_target, size as _, std::ptr::null() as *const _, gl_usage);
-            ctx.cache.restore_buffer_binding(gl_target);
-        }
+        Self::uninitialized(ctx, buffer_type, index_type, Usage::Stream, size, true)
+    }
 
-        Buffer {
-            gl_buf,
-            buffer_type,
+    pub fn index_stream(ctx: &mut Context, index_type: IndexType, size: usize) -> Buffer {
+        Self::uninitialized(
+            ctx,
+            BufferType::IndexBuffer,
+            Some(index_type),
+            Usage::Stream,
             size,
-            index_type,
-        }
+            true,
+        )
     }
 
-    pub fn index_stream(ctx: &mut Context, index_type: IndexType, size: usize) -> Buffer {
-        let gl_target = gl_buffer_target(&BufferType::IndexBuffer);
-        let gl_usage = gl_usage(&Usage::Stream);
+    /// Buffer updated occasionally, in parts with `update_range`.
+    ///
+    /// Index buffers are of `IndexType::Short`.
+    pub fn dynamic(ctx: &mut Context, buffer_type: BufferType, size: usize) -> Buffer {
+        let index_type = if buffer_type == BufferType::IndexBuffer {
+            Some(IndexType::Short)
+        } else {
+            None
+        };
+
+        Self::uninitialized(ctx, buffer_type, index_type, Usage::Dynamic, size, true)
+    }
+
+    /// With `shadow` the buffer gets a CPU copy for `resize`, if the context needs one.
+    fn uninitialized(
+        ctx: &mut Context,
+        buffer_type: BufferType,
+        index_type: Option<IndexType>,
+        usage: Usage,
+        size: usize,
+        shadow: bool,
+    ) -> Buffer {
+        let gl_target = gl_buffer_target(&buffer_type);
+        let gl_usage = gl_usage(&usage);
         let mut gl_buf: u32 = 0;
 
         unsafe {
@@ -1808,35 +4243,171 @@ impl Buffer {
             glBufferData(gl_target, size as _, std::ptr::null() as *const _, gl_usage);
             ctx.cache.restore_buffer_binding(gl_target);
         }
+        let shadow = if shadow && !ctx.features.copy_buffer {
+            Some(vec![0; size])
+        } else {
+            None
+        };
+        registry::track_buffer(gl_buf, size, shadow);
 
         Buffer {
             gl_buf,
-            buffer_type: BufferType::IndexBuffer,
+            buffer_type,
+            usage,
             size,
-            index_type: Some(index_type),
+            index_type,
+            registry_id: 0,
         }
     }
+
+    /// Same as `update_range` at offset 0.
     pub fn update<T>(&self, ctx: &mut Context, data: &[T]) {
+        self.update_range(ctx, 0, data);
+    }
+
+    /// Write `data` starting `offset` bytes into the buffer, the rest of the buffer is kept.
+    ///
+    /// A `stream` buffer written at offset 0 is orphaned first: the driver gets fresh storage
+    /// instead of waiting for the draw calls still reading the old data. Writes at other offsets
+    /// append to the current storage, so a frame is usually written front to back.
+    ///
+    /// Panics at offset 0 of the persistently mapped buffer of a `RingBuffer`,
+    /// its storage can't be orphaned.
+    pub fn update_range<T>(&self, ctx: &mut Context, offset: usize, data: &[T]) {
         if self.buffer_type == BufferType::IndexBuffer {
             assert!(self.index_type.is_some());
             assert!(self.index_type.unwrap() == IndexType::for_type::<T>());
         };
 
         let size = mem::size_of_val(data);
+        let buffer_size = self.size();
+
+        assert!(offset + size <= buffer_size);
 
-        assert!(size <= self.size);
+        let orphan = self.usage == Usage::Stream && offset == 0;
+        assert!(
+            !(orphan && registry::ring_buffer(self.gl_buf) == Some(true)),
+            "The persistently mapped buffer of a RingBuffer can't be orphaned"
+        );
 
         let gl_target = gl_buffer_target(&self.buffer_type);
         ctx.cache.store_buffer_binding(gl_target);
         ctx.cache
             .bind_buffer(gl_target, self.gl_buf, self.index_type);
-        unsafe { glBufferSubData(gl_target, 0, size as _, data.as_ptr() as *const _) };
+        unsafe {
+            if orphan {
+                glBufferData(
+                    gl_target,
+                    buffer_size as _,
+                    std::ptr::null() as *const _,
+                    gl_usage(&self.usage),
+                );
+            }
+            glBufferSubData(gl_target, offset as _, size as _, data.as_ptr() as *const _)
+        };
         ctx.cache.restore_buffer_binding(gl_target);
+
+        registry::with_buffer_shadow(self.gl_buf, |shadow| {
+            let bytes = unsafe { std::slice::from_raw_parts(data.as_ptr() as *const u8, size) };
+            shadow[offset..offset + size].copy_from_slice(bytes);
+        });
+    }
+
+    /// Reallocate the buffer storage with `size` bytes. With `keep_contents` the first
+    /// `min(size, self.size())` bytes are preserved, otherwise the new storage is undefined.
+    ///
+    /// The GL buffer object stays the same, so `Bindings` and other copies of this
+    /// `Buffer` keep working and see the new `size()`.
+    ///
+    /// Contents are copied on the GPU with `features.copy_buffer`, otherwise from a CPU copy
+    /// of everything written with `update_range`. `immutable` buffers and the buffer of
+    /// a `RingBuffer` can't be resized.
+    pub fn resize(&mut self, ctx: &mut Context, size: usize, keep_contents: bool) {
+        assert!(
+            self.usage != Usage::Immutable,
+            "Immutable buffers can't be resized"
+        );
+        assert!(
+            registry::ring_buffer(self.gl_buf).is_none(),
+            "The buffer of a RingBuffer can't be resized"
+        );
+
+        let keep = if keep_contents {
+            self.size().min(size)
+        } else {
+            0
+        };
+        let gl_usage = gl_usage(&self.usage);
+
+        if ctx.features.copy_buffer {
+            // the copy targets are not tracked by the cache and not a part of VAO state,
+            // unlike GL_ELEMENT_ARRAY_BUFFER
+            unsafe {
+                let mut tmp = 0;
+                if keep > 0 {
+                    glGenBuffers(1, &mut tmp as *mut _);
+                    glBindBuffer(GL_COPY_WRITE_BUFFER, tmp);
+                    glBufferData(
+                        GL_COPY_WRITE_BUFFER,
+                        keep as _,
+                        std::ptr::null(),
+                        GL_STREAM_COPY,
+                    );
+                }
+                glBindBuffer(GL_COPY_READ_BUFFER, self.gl_buf);
+                if keep > 0 {
+                    glCopyBufferSubData(GL_COPY_READ_BUFFER, GL_COPY_WRITE_BUFFER, 0, 0, keep as _);
+                }
+                glBufferData(GL_COPY_READ_BUFFER, size as _, std::ptr::null(), gl_usage);
+                if keep > 0 {
+                    glCopyBufferSubData(GL_COPY_WRITE_BUFFER, GL_COPY_READ_BUFFER, 0, 0, keep as _);
+                    glBindBuffer(GL_COPY_WRITE_BUFFER, 0);
+                    glDeleteBuffers(1, &tmp as *const _);
+                }
+                glBindBuffer(GL_COPY_READ_BUFFER, 0);
+            }
+        } else {
+            let gl_target = gl_buffer_target(&self.buffer_type);
+            ctx.cache.store_buffer_binding(gl_target);
+            ctx.cache
+                .bind_buffer(gl_target, self.gl_buf, self.index_type);
+            let mut shadow = registry::take_buffer_shadow(self.gl_buf).unwrap_or_default();
+            shadow.resize(size, 0);
+            unsafe {
+                glBufferData(gl_target, size as _, std::ptr::null(), gl_usage);
+                if keep > 0 {
+                    glBufferSubData(gl_target, 0, keep as _, shadow.as_ptr() as *const _);
+                }
+            }
+            if !keep_contents {
+                shadow.iter_mut().for_each(|byte| *byte = 0);
+            }
+            ctx.cache.restore_buffer_binding(gl_target);
+            registry::track_buffer(self.gl_buf, size, Some(shadow));
+        }
+
+        registry::set_buffer_size(self.gl_buf, size);
+        self.size = size;
     }
 
     /// Size of buffer in bytes
     pub fn size(&self) -> usize {
-        self.size
+        // `resize` through another copy of the handle only updates the registry
+        registry::buffer_size(self.gl_buf).unwrap_or(self.size)
+    }
+
+    /// GL name of the buffer, a registered buffer gets a new one on every context restoration.
+    fn gl_name(&self) -> GLuint {
+        if self.registry_id == 0 {
+            return self.gl_buf;
+        }
+        registry::buffer_name(self.registry_id).unwrap_or(self.gl_buf)
+    }
+
+    /// Name the buffer in driver messages and frame debuggers.
+    /// Does nothing without `features.debug_output`.
+    pub fn label(&self, ctx: &mut Context, label: &str) {
+        ctx.object_label(GL_BUFFER, self.gl_name(), label);
     }
 
     /// Delete GPU buffer, leaving handle unmodified.
@@ -1847,7 +4418,9 @@ impl Buffer {
     /// There is no protection against using deleted textures later. However its not an UB in OpenGl and thats why
     /// this function is not marked as unsafe
     pub fn delete(&self) {
-        unsafe { glDeleteBuffers(1, &self.gl_buf as *const _) }
+        let gl_buf = registry::unregister_buffer(self.registry_id).unwrap_or(self.gl_buf);
+        registry::untrack_buffer(gl_buf);
+        unsafe { glDeleteBuffers(1, &gl_buf as *const _) }
     }
 }
 
@@ -1958,17 +4531,17 @@ impl ElapsedQuery {
     ///
     /// Use [`ElapsedQuery::is_supported()`] to check if functionality is available and the method can be called.
     pub fn get_result(&self) -> u64 {
-        // let mut time: GLuint64 = 0;
-        // assert!(self.gl_query != 0);
-        // unsafe { glGetQueryObjectui64v(self.gl_query, GL_QUERY_RESULT, &mut time) };
-        // time
-        0
+        let mut time: GLuint64 = 0;
+        assert!(self.gl_query != 0);
+        unsafe { glGetQueryObjectui64v(self.gl_query, GL_QUERY_RESULT, &mut time) };
+        time
     }
 
     /// Reports whenever elapsed timer is supported and other methods can be invoked.
+    ///
+    /// Same as `features.timer_query` of the last created context.
     pub fn is_supported() -> bool {
-        unimplemented!();
-        //unsafe { sapp_is_elapsed_timer_supported() }
+        query::TIMER_QUERY.load(std::sync::atomic::Ordering::Relaxed)
     }
 
     /// Reports whenever result of submitted query is available for retrieval with
@@ -1979,17 +4552,15 @@ impl ElapsedQuery {
     ///
     /// Use [`ElapsedQuery::is_supported()`] to check if functionality is available and the method can be called.
     pub fn is_available(&self) -> bool {
-        // let mut available: GLint = 0;
+        let mut available: GLint = 0;
 
-        // // begin_query was not called yet
-        // if self.gl_query == 0 {
-        //     return false;
-        // }
-
-        //unsafe { glGetQueryObjectiv(self.gl_query, GL_QUERY_RESULT_AVAILABLE, &mut available) };
-        //available != 0
+        // begin_query was not called yet
+        if self.gl_query == 0 {
+            return false;
+        }
 
-        false
+        unsafe { glGetQueryObjectiv(self.gl_query, GL_QUERY_RESULT_AVAILABLE, &mut available) };
+        available != 0
     }
 
     /// Delete query.
@@ -2002,3 +4573,165 @@ impl ElapsedQuery {
         self.gl_query = 0;
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn buffer_strides_follow_attributes() {
+        let layout = [BufferLayout::default(), BufferLayout::default()];
+        let attributes = [
+            VertexAttribute::new("pos", VertexFormat::Float3),
+            VertexAttribute::new("color", VertexFormat::Byte4Norm),
+            VertexAttribute::with_buffer("inst_pos", VertexFormat::Float2, 1),
+        ];
+        assert_eq!(buffer_strides(&layout, &attributes), vec![16, 8]);
+    }
+
+    #[test]
+    fn buffer_strides_with_offsets() {
+        let layout = [BufferLayout::default()];
+        // the stride is the end of the furthest attribute, not of the last one
+        let attributes = [
+            VertexAttribute::new("uv", VertexFormat::Float2).offset(12),
+            VertexAttribute::new("pos", VertexFormat::Float3).offset(0),
+            VertexAttribute::new("normal", VertexFormat::Float3),
+        ];
+        assert_eq!(buffer_strides(&layout, &attributes), vec![24]);
+
+        let attributes = [
+            VertexAttribute::new("pos", VertexFormat::Float3).offset(0),
+            VertexAttribute::new("uv", VertexFormat::Float2).offset(20),
+        ];
+        assert_eq!(buffer_strides(&layout, &attributes), vec![28]);
+    }
+
+    #[test]
+    fn buffer_strides_keep_explicit_stride() {
+        let layout = [
+            BufferLayout {
+                stride: 64,
+                ..Default::default()
+            },
+            BufferLayout::default(),
+        ];
+        let attributes = [VertexAttribute::new("pos", VertexFormat::Float2)];
+        // a buffer without attributes gets a zero stride
+        assert_eq!(buffer_strides(&layout, &attributes), vec![64, 0]);
+    }
+
+    #[test]
+    fn validate_strides_reports_first_buffer_over_limit() {
+        let layout = [
+            BufferLayout::default(),
+            BufferLayout {
+                stride: 4096,
+                ..Default::default()
+            },
+            BufferLayout {
+                stride: 8192,
+                ..Default::default()
+            },
+        ];
+        let attributes = [VertexAttribute::new("pos", VertexFormat::Float4)];
+
+        assert!(validate_strides(&layout, &attributes, 8192).is_ok());
+        assert!(matches!(
+            validate_strides(&layout, &attributes, 2048),
+            Err(ShaderError::VertexStrideTooLarge {
+                buffer_index: 1,
+                stride: 4096,
+                max_stride: 2048,
+            })
+        ));
+    }
+
+    #[test]
+    fn validate_strides_reports_missing_buffer() {
+        let layout = [BufferLayout::default()];
+        let attributes = [
+            VertexAttribute::new("pos", VertexFormat::Float3),
+            VertexAttribute::with_buffer("inst_pos", VertexFormat::Float2, 1),
+        ];
+
+        assert_eq!(buffer_strides(&layout, &attributes), vec![12]);
+        match validate_strides(&layout, &attributes, 2048) {
+            Err(ShaderError::MetaMismatch(message)) => assert_eq!(
+                message,
+                "attribute \"inst_pos\" uses buffer 1, but there are only 1 buffer layouts"
+            ),
+            other => panic!("unexpected {:?}", other),
+        }
+    }
+
+    #[test]
+    fn parse_gl_versions() {
+        let cases = [
+            ("4.6 (Core Profile) Mesa 22.3.6", (4, 6)),
+            ("4.6.0 NVIDIA 535.54.03", (4, 6)),
+            ("OpenGL ES 3.2 Mesa 22.3.6", (3, 2)),
+            ("OpenGL ES 2.0 (ANGLE 2.1.0)", (2, 0)),
+            ("WebGL 1.0 (OpenGL ES 2.0 Chromium)", (1, 0)),
+            ("2.1 Metal - 76.3", (2, 1)),
+            ("", (0, 0)),
+        ];
+        for (version, parsed) in &cases {
+            assert_eq!(parse_gl_version(version), *parsed, "{}", version);
+        }
+    }
+
+    #[test]
+    fn plain_uniforms_are_packed() {
+        let layout = UniformBlockLayout::new(vec![
+            UniformDesc::new("time", UniformType::Float1),
+            UniformDesc::new("offset", UniformType::Float3),
+            UniformDesc::new("weights", UniformType::Float2).array(3),
+        ]);
+        assert_eq!(layout.offsets(), (vec![(0, 4), (4, 12), (16, 8)], 40));
+        assert_eq!(layout.size(), 40);
+    }
+
+    #[test]
+    fn std140_offsets() {
+        let layout = UniformBlockLayout::std140(
+            "Uniforms",
+            vec![
+                UniformDesc::new("time", UniformType::Float1),
+                // vec2 is 8 byte aligned
+                UniformDesc::new("offset", UniformType::Float2),
+                // vec3 is 16 byte aligned and leaves room for a float
+                UniformDesc::new("color", UniformType::Float3),
+                UniformDesc::new("scale", UniformType::Float1),
+                UniformDesc::new("mvp", UniformType::Mat4),
+                UniformDesc::new("count", UniformType::Int1),
+            ],
+        );
+        assert_eq!(
+            layout.offsets(),
+            (
+                vec![(0, 4), (8, 8), (16, 12), (28, 4), (32, 64), (96, 4)],
+                100
+            )
+        );
+    }
+
+    #[test]
+    fn std140_arrays_have_16_byte_elements() {
+        let layout = UniformBlockLayout::std140(
+            "Uniforms",
+            vec![
+                UniformDesc::new("time", UniformType::Float1),
+                UniformDesc::new("weights", UniformType::Float1).array(3),
+                UniformDesc::new("after_weights", UniformType::Float1),
+                UniformDesc::new("lights", UniformType::Float4).array(2),
+                UniformDesc::new("after_lights", UniformType::Float1),
+            ],
+        );
+        // the last element is padded too, as in `[[f32; 4]; 3]`
+        assert_eq!(
+            layout.offsets(),
+            (vec![(0, 4), (16, 16), (64, 4), (80, 16), (112, 4)], 116)
+        );
+    }
+}
@@ -0,0 +1,265 @@
+//! KHR_debug driver messages, object labels and debug groups.
+
+use std::{ffi::CStr, os::raw::c_void};
+
+use crate::native::gl::*;
+
+#[cfg(any(target_arch = "wasm32", target_os = "macos", target_os = "ios"))]
+use crate::native::debug_stab::*;
+
+/// Labels and group names are truncated to fit the minimal GL_MAX_LABEL_LENGTH,
+/// 256 bytes with the terminator.
+const MAX_LABEL_LENGTH: usize = 255;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum DebugSource {
+    Api,
+    WindowSystem,
+    ShaderCompiler,
+    ThirdParty,
+    /// Messages and groups of `Context::push_debug_group` and labelled pipelines.
+    Application,
+    Other,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum DebugType {
+    Error,
+    DeprecatedBehavior,
+    UndefinedBehavior,
+    Portability,
+    Performance,
+    Marker,
+    PushGroup,
+    PopGroup,
+    Other,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, PartialOrd)]
+pub enum DebugSeverity {
+    High,
+    Medium,
+    Low,
+    Notification,
+}
+
+impl DebugSource {
+    fn from_gl(source: GLenum) -> DebugSource {
+        match source {
+            GL_DEBUG_SOURCE_API => DebugSource::Api,
+            GL_DEBUG_SOURCE_WINDOW_SYSTEM => DebugSource::WindowSystem,
+            GL_DEBUG_SOURCE_SHADER_COMPILER => DebugSource::ShaderCompiler,
+            GL_DEBUG_SOURCE_THIRD_PARTY => DebugSource::ThirdParty,
+            GL_DEBUG_SOURCE_APPLICATION => DebugSource::Application,
+            _ => DebugSource::Other,
+        }
+    }
+}
+
+impl DebugType {
+    fn from_gl(type_: GLenum) -> DebugType {
+        match type_ {
+            GL_DEBUG_TYPE_ERROR => DebugType::Error,
+            GL_DEBUG_TYPE_DEPRECATED_BEHAVIOR => DebugType::DeprecatedBehavior,
+            GL_DEBUG_TYPE_UNDEFINED_BEHAVIOR => DebugType::UndefinedBehavior,
+            GL_DEBUG_TYPE_PORTABILITY => DebugType::Portability,
+            GL_DEBUG_TYPE_PERFORMANCE => DebugType::Performance,
+            GL_DEBUG_TYPE_MARKER => DebugType::Marker,
+            GL_DEBUG_TYPE_PUSH_GROUP => DebugType::PushGroup,
+            GL_DEBUG_TYPE_POP_GROUP => DebugType::PopGroup,
+            _ => DebugType::Other,
+        }
+    }
+}
+
+impl DebugSeverity {
+    fn from_gl(severity: GLenum) -> DebugSeverity {
+        match severity {
+            GL_DEBUG_SEVERITY_HIGH => DebugSeverity::High,
+            GL_DEBUG_SEVERITY_MEDIUM => DebugSeverity::Medium,
+            GL_DEBUG_SEVERITY_LOW => DebugSeverity::Low,
+            _ => DebugSeverity::Notification,
+        }
+    }
+}
+
+/// A message reported by the driver, or inserted by miniquad itself.
+#[derive(Clone, Copy, Debug)]
+pub struct DebugMessage<'a> {
+    pub source: DebugSource,
+    pub message_type: DebugType,
+    /// Driver specific message id.
+    pub id: u32,
+    pub severity: DebugSeverity,
+    pub message: &'a str,
+}
+
+pub(crate) type DebugCallback = Box<dyn FnMut(&DebugMessage)>;
+
+/// Used until `Context::set_debug_callback` is called. Notifications are skipped,
+/// some drivers report every buffer upload.
+pub(crate) fn default_callback() -> DebugCallback {
+    Box::new(|message: &DebugMessage| {
+        if message.severity == DebugSeverity::Notification {
+            return;
+        }
+        let text = format!(
+            "GL {:?} {:?} ({:?}, {}): {}",
+            message.severity, message.message_type, message.source, message.id, message.message
+        );
+
+        let level = match message.severity {
+            DebugSeverity::High => LogLevel::Error,
+            DebugSeverity::Medium => LogLevel::Warn,
+            _ => LogLevel::Info,
+        };
+        log(level, "miniquad::gl", &text);
+    })
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) enum LogLevel {
+    Error,
+    Warn,
+    Info,
+}
+
+/// Print a message of miniquad itself. Goes through the "log-impl" macros when the feature
+/// is enabled, otherwise to the platform console: stderr is lost on wasm and android.
+pub(crate) fn log(level: LogLevel, target: &str, text: &str) {
+    #[cfg(feature = "log-impl")]
+    {
+        use crate::log::Level;
+
+        let level = match level {
+            LogLevel::Error => Level::Error,
+            LogLevel::Warn => Level::Warn,
+            LogLevel::Info => Level::Info,
+        };
+        crate::log::__private_api_log_lit(text, level, &(target, module_path!(), file!(), line!()));
+    }
+    #[cfg(not(feature = "log-impl"))]
+    {
+        let _ = target;
+        console_log(level, text);
+    }
+}
+
+#[cfg(all(not(feature = "log-impl"), target_arch = "wasm32"))]
+fn console_log(level: LogLevel, text: &str) {
+    use crate::native::wasm;
+
+    let log_fn = match level {
+        LogLevel::Error => wasm::console_error,
+        LogLevel::Warn => wasm::console_warn,
+        LogLevel::Info => wasm::console_info,
+    };
+    let text = std::ffi::CString::new(text.replace('\0', "")).unwrap();
+    unsafe { log_fn(text.as_ptr()) };
+}
+
+#[cfg(all(not(feature = "log-impl"), target_os = "android"))]
+fn console_log(level: LogLevel, text: &str) {
+    use crate::native::android;
+
+    let log_fn = match level {
+        LogLevel::Error => android::console_error,
+        LogLevel::Warn => android::console_warn,
+        LogLevel::Info => android::console_info,
+    };
+    let text = std::ffi::CString::new(text.replace('\0', "")).unwrap();
+    unsafe { log_fn(text.as_ptr()) };
+}
+
+#[cfg(all(
+    not(feature = "log-impl"),
+    not(any(target_arch = "wasm32", target_os = "android"))
+))]
+fn console_log(_level: LogLevel, text: &str) {
+    eprintln!("{}", text);
+}
+
+extern "system" fn debug_proc(
+    source: GLenum,
+    type_: GLenum,
+    id: GLuint,
+    severity: GLenum,
+    length: GLsizei,
+    message: *const GLchar,
+    user_param: *mut c_void,
+) {
+    // user_param is the `DebugCallback` registered with `install_callback`
+    let callback = unsafe { &mut *(user_param as *mut DebugCallback) };
+    let message = unsafe {
+        if length < 0 {
+            CStr::from_ptr(message).to_bytes()
+        } else {
+            std::slice::from_raw_parts(message as *const u8, length as usize)
+        }
+    };
+    callback(&DebugMessage {
+        source: DebugSource::from_gl(source),
+        message_type: DebugType::from_gl(type_),
+        id,
+        severity: DebugSeverity::from_gl(severity),
+        message: &String::from_utf8_lossy(message),
+    });
+}
+
+/// Route the driver messages to `callback`, which has to stay at the same address
+/// until another one is installed.
+pub(crate) unsafe fn install_callback(callback: &mut DebugCallback) {
+    glDebugMessageCallback(
+        Some(debug_proc),
+        callback as *mut DebugCallback as *const c_void,
+    );
+}
+
+fn truncate(label: &str) -> &str {
+    if label.len() <= MAX_LABEL_LENGTH {
+        return label;
+    }
+    let mut end = MAX_LABEL_LENGTH;
+    while !label.is_char_boundary(end) {
+        end -= 1;
+    }
+    &label[..end]
+}
+
+/// glObjectLabel, `identifier` is GL_BUFFER, GL_TEXTURE, GL_PROGRAM or GL_FRAMEBUFFER.
+pub(crate) unsafe fn object_label(identifier: GLenum, name: GLuint, label: &str) {
+    let label = truncate(label);
+    glObjectLabel(
+        identifier,
+        name,
+        label.len() as _,
+        label.as_ptr() as *const _,
+    );
+}
+
+pub(crate) unsafe fn push_group(name: &str) {
+    let name = truncate(name);
+    glPushDebugGroup(
+        GL_DEBUG_SOURCE_APPLICATION,
+        0,
+        name.len() as _,
+        name.as_ptr() as *const _,
+    );
+}
+
+pub(crate) unsafe fn pop_group() {
+    glPopDebugGroup();
+}
+
+/// A `DebugType::Marker` notification, shown inline by frame debuggers.
+pub(crate) unsafe fn insert_marker(message: &str) {
+    let message = truncate(message);
+    glDebugMessageInsert(
+        GL_DEBUG_SOURCE_APPLICATION,
+        GL_DEBUG_TYPE_MARKER,
+        0,
+        GL_DEBUG_SEVERITY_NOTIFICATION,
+        message.len() as _,
+        message.as_ptr() as *const _,
+    );
+}
@@ -0,0 +1,643 @@
+//! GLSL preprocessor running before the driver's one: resolves `#include`,
+//! injects `#define`s and the `#version` header for the current context.
+//!
+//! Sources are written in GLSL 100 (the webgl1 dialect, as in the examples),
+//! for GLSL 300 es and desktop 130+ `attribute`, `varying`, `texture2D`, `gl_FragColor`
+//! and `gl_FragData` are rewritten into their modern counterparts.
+//!
+//! `gl_FragData[n]` becomes an output array of `gl_MaxDrawBuffers` elements at
+//! locations 0, 1, ..., matching the color attachments of a `RenderPass`.
+//! Which of the two outputs is declared depends on whether `gl_FragData` appears
+//! in the fragment shader, comments included.
+
+use crate::Context;
+
+use super::{ShaderError, ShaderType};
+
+/// GLSL dialect shader sources are translated to.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum GlslVersion {
+    /// `#version 100`: gles2, webgl1 and gl2.1.
+    Glsl100,
+    /// `#version 300 es`: gles3.
+    Glsl300Es,
+    /// `#version 130`: desktop gl3.0.
+    Glsl130,
+    /// `#version 140`: desktop gl3.1.
+    Glsl140,
+    /// `#version 150`: desktop gl3.2, as requested on macOS and Windows.
+    Glsl150,
+    /// `#version 330`: desktop gl3.3+.
+    Glsl330,
+}
+
+impl GlslVersion {
+    /// Dialect for a `GL_VERSION` string. Desktop contexts older than 3.0 get GLSL 100,
+    /// as with `GL_ARB_ES2_compatibility`.
+    pub(crate) fn from_gl_version(version: &str) -> GlslVersion {
+        if let Some(es_version) = version.strip_prefix("OpenGL ES") {
+            // "OpenGL ES 3.2 Mesa", "OpenGL ES-CM 1.1"
+            return match es_version.trim_start_matches(|c: char| !c.is_ascii_digit()) {
+                v if v.starts_with('2') || v.starts_with('1') => GlslVersion::Glsl100,
+                _ => GlslVersion::Glsl300Es,
+            };
+        }
+
+        let mut numbers = version
+            .split(|c: char| !c.is_ascii_digit())
+            .map(|n| n.parse::<u32>().unwrap_or(0));
+        let major = numbers.next().unwrap_or(0);
+        let minor = numbers.next().unwrap_or(0);
+        match (major, minor) {
+            (3, 0) => GlslVersion::Glsl130,
+            (3, 1) => GlslVersion::Glsl140,
+            (3, 2) => GlslVersion::Glsl150,
+            version if version >= (3, 3) => GlslVersion::Glsl330,
+            _ => GlslVersion::Glsl100,
+        }
+    }
+
+    fn version_directive(&self) -> &'static str {
+        match self {
+            GlslVersion::Glsl100 => "#version 100",
+            GlslVersion::Glsl300Es => "#version 300 es",
+            GlslVersion::Glsl130 => "#version 130",
+            GlslVersion::Glsl140 => "#version 140",
+            GlslVersion::Glsl150 => "#version 150",
+            GlslVersion::Glsl330 => "#version 330",
+        }
+    }
+
+    /// Declarations going after `#version` and `#extension`s.
+    /// Outputs without a location are only allowed one per shader, hence `frag_data`.
+    /// Desktop GLSL before 330 has no locations at all, the only output still gets 0.
+    fn prelude(&self, shader_type: ShaderType, frag_data: bool) -> &'static [&'static str] {
+        match (self, shader_type, frag_data) {
+            (_, ShaderType::Vertex, _) => &[],
+            (GlslVersion::Glsl100, ShaderType::Fragment, _) => &["precision mediump float;"],
+            (GlslVersion::Glsl300Es, ShaderType::Fragment, false) => {
+                &["precision highp float;", "out vec4 miniquad_FragColor;"]
+            }
+            (GlslVersion::Glsl300Es, ShaderType::Fragment, true) => &[
+                "precision highp float;",
+                "out vec4 miniquad_FragData[gl_MaxDrawBuffers];",
+            ],
+            (_, ShaderType::Fragment, false) => &["out vec4 miniquad_FragColor;"],
+            (_, ShaderType::Fragment, true) => &["out vec4 miniquad_FragData[gl_MaxDrawBuffers];"],
+        }
+    }
+
+    fn rewrite(&self, shader_type: ShaderType, identifier: &str) -> Option<&'static str> {
+        if *self == GlslVersion::Glsl100 {
+            return None;
+        }
+
+        match (shader_type, identifier) {
+            (ShaderType::Vertex, "attribute") => Some("in"),
+            (ShaderType::Vertex, "varying") => Some("out"),
+            (ShaderType::Fragment, "varying") => Some("in"),
+            (ShaderType::Fragment, "gl_FragColor") => Some("miniquad_FragColor"),
+            (ShaderType::Fragment, "gl_FragData") => Some("miniquad_FragData"),
+            (_, "texture2D") | (_, "textureCube") => Some("texture"),
+            (_, "texture2DProj") => Some("textureProj"),
+            (_, "texture2DLod")
+            | (_, "textureCubeLod")
+            | (_, "texture2DLodEXT")
+            | (_, "textureCubeLodEXT") => Some("textureLod"),
+            _ => None,
+        }
+    }
+}
+
+type IncludeResolver = Box<dyn Fn(&str) -> Option<String>>;
+
+/// Shader source preprocessor, used through `Shader::new_preprocessed`.
+///
+/// ```ignore
+/// let preprocessor = Preprocessor::new(ctx)
+///     .define("SHADOWS", "1")
+///     .include_resolver(|path| std::fs::read_to_string(format!("shaders/{}", path)).ok());
+/// let shader = Shader::new_preprocessed(ctx, &preprocessor, VERTEX, FRAGMENT, meta)?;
+/// ```
+pub struct Preprocessor {
+    version: GlslVersion,
+    defines: Vec<(String, String)>,
+    include_resolver: Option<IncludeResolver>,
+}
+
+/// Preprocessed source along with the origin of each line.
+pub struct PreprocessedSource {
+    pub source: String,
+    files: Vec<String>,
+    /// (file index, 1-based line) for each line of `source`, None for the generated ones.
+    lines: Vec<Option<(usize, usize)>>,
+}
+
+/// Output lines with their origins, as in `PreprocessedSource::lines`.
+type Lines = Vec<(String, Option<(usize, usize)>)>;
+
+#[derive(Default)]
+struct Output {
+    files: Vec<String>,
+    /// "#extension" should precede any code, so they are moved right after "#version"
+    extensions: Lines,
+    body: Lines,
+    /// `gl_FragData` is used instead of `gl_FragColor`
+    frag_data: bool,
+}
+
+impl Preprocessor {
+    /// Preprocessor targeting the GLSL dialect of the current context.
+    pub fn new(ctx: &Context) -> Preprocessor {
+        Preprocessor::with_version(ctx.features.glsl_version)
+    }
+
+    pub fn with_version(version: GlslVersion) -> Preprocessor {
+        Preprocessor {
+            version,
+            defines: vec![],
+            include_resolver: None,
+        }
+    }
+
+    pub fn version(&self) -> GlslVersion {
+        self.version
+    }
+
+    /// Add `#define name value` in front of every shader.
+    pub fn define(mut self, name: &str, value: &str) -> Preprocessor {
+        self.defines.push((name.to_string(), value.to_string()));
+        self
+    }
+
+    /// Source of `#include "path"` by its path.
+    /// Without a resolver includes are read from the filesystem, relative to the including file,
+    /// this is not available on wasm.
+    pub fn include_resolver<F>(mut self, resolver: F) -> Preprocessor
+    where
+        F: Fn(&str) -> Option<String> + 'static,
+    {
+        self.include_resolver = Some(Box::new(resolver));
+        self
+    }
+
+    /// `name` is used in error messages, usually it is the source file path.
+    pub fn process(
+        &self,
+        shader_type: ShaderType,
+        name: &str,
+        source: &str,
+    ) -> Result<PreprocessedSource, ShaderError> {
+        let mut output = Output::default();
+        self.process_file(shader_type, name, source, &mut vec![], &mut output)?;
+
+        let mut res = PreprocessedSource {
+            source: String::new(),
+            files: output.files,
+            lines: vec![],
+        };
+        res.push_line(self.version.version_directive(), None);
+        for (line, origin) in output.extensions {
+            res.push_line(&line, origin);
+        }
+        for line in self.version.prelude(shader_type, output.frag_data) {
+            res.push_line(line, None);
+        }
+        for (name, value) in &self.defines {
+            res.push_line(&format!("#define {} {}", name, value), None);
+        }
+        for (line, origin) in output.body {
+            res.push_line(&line, origin);
+        }
+
+        Ok(res)
+    }
+
+    fn process_file(
+        &self,
+        shader_type: ShaderType,
+        name: &str,
+        source: &str,
+        include_stack: &mut Vec<String>,
+        output: &mut Output,
+    ) -> Result<(), ShaderError> {
+        let file = output.files.len();
+        output.files.push(name.to_string());
+        include_stack.push(name.to_string());
+
+        for (n, line) in source.lines().enumerate() {
+            let error = |message: String| ShaderError::PreprocessError {
+                file: name.to_string(),
+                line: n + 1,
+                message,
+            };
+            let directive = line.trim_start();
+
+            // the header has the version already
+            if directive.starts_with("#version") {
+                continue;
+            }
+            // pragma once is the same as a guard, files on the include stack are errors anyway
+            if directive.starts_with("#pragma once") {
+                if output.files[..file].iter().any(|f| f == name) {
+                    break;
+                }
+                continue;
+            }
+            if directive.starts_with("#extension") {
+                // derivatives, explicit lods and draw buffers are in the core of modern GLSL
+                let core = directive.contains("GL_OES_standard_derivatives")
+                    || directive.contains("GL_EXT_shader_texture_lod")
+                    || directive.contains("GL_EXT_draw_buffers");
+                if self.version == GlslVersion::Glsl100 || !core {
+                    output
+                        .extensions
+                        .push((line.to_string(), Some((file, n + 1))));
+                }
+                continue;
+            }
+
+            if let Some(include) = directive.strip_prefix("#include") {
+                let include = include.trim();
+                let path = include
+                    .strip_prefix('"')
+                    .and_then(|path| path.strip_suffix('"'))
+                    .or_else(|| {
+                        include
+                            .strip_prefix('<')
+                            .and_then(|path| path.strip_suffix('>'))
+                    })
+                    .ok_or_else(|| error(format!("malformed include: {}", include)))?;
+
+                let (path, source) = self
+                    .resolve(name, path)
+                    .ok_or_else(|| error(format!("can't include \"{}\"", path)))?;
+                if include_stack.contains(&path) {
+                    return Err(error(format!("recursive include of \"{}\"", path)));
+                }
+                self.process_file(shader_type, &path, &source, include_stack, output)?;
+                continue;
+            }
+
+            let frag_data = &mut output.frag_data;
+            let line = rewrite_identifiers(line, |identifier| {
+                *frag_data |= identifier == "gl_FragData";
+                self.version.rewrite(shader_type, identifier)
+            });
+            output.body.push((line, Some((file, n + 1))));
+        }
+
+        include_stack.pop();
+        Ok(())
+    }
+
+    /// Preprocess (name, source) of both stages and pass the results to `f`,
+    /// mapping its compilation errors back to the original sources.
+    pub(crate) fn with_sources<T, F>(
+        &self,
+        vertex: (&str, &str),
+        fragment: (&str, &str),
+        f: F,
+    ) -> Result<T, ShaderError>
+    where
+        F: FnOnce(&str, &str) -> Result<T, ShaderError>,
+    {
+        let vertex = self.process(ShaderType::Vertex, vertex.0, vertex.1)?;
+        let fragment = self.process(ShaderType::Fragment, fragment.0, fragment.1)?;
+
+        f(&vertex.source, &fragment.source).map_err(|err| match err {
+            ShaderError::CompilationError {
+                shader_type,
+                error_message,
+            } => {
+                let source = match shader_type {
+                    ShaderType::Vertex => &vertex,
+                    ShaderType::Fragment => &fragment,
+                };
+                ShaderError::CompilationError {
+                    shader_type,
+                    error_message: source.map_log(&error_message),
+                }
+            }
+            err => err,
+        })
+    }
+
+    /// Name of the included file for error messages and its source.
+    fn resolve(&self, includer: &str, path: &str) -> Option<(String, String)> {
+        if let Some(resolver) = &self.include_resolver {
+            return resolver(path).map(|source| (path.to_string(), source));
+        }
+
+        #[cfg(not(target_arch = "wasm32"))]
+        {
+            let path = std::path::Path::new(includer)
+                .parent()
+                .unwrap_or_else(|| std::path::Path::new(""))
+                .join(path);
+            let source = std::fs::read_to_string(&path).ok()?;
+            Some((path.to_string_lossy().into_owned(), source))
+        }
+
+        #[cfg(target_arch = "wasm32")]
+        {
+            let _ = includer;
+            None
+        }
+    }
+}
+
+impl PreprocessedSource {
+    fn push_line(&mut self, line: &str, origin: Option<(usize, usize)>) {
+        self.source.push_str(line);
+        self.source.push('\n');
+        self.lines.push(origin);
+    }
+
+    /// Original "file:line" for a 1-based line of the preprocessed source.
+    pub fn origin(&self, line: usize) -> Option<(&str, usize)> {
+        let (file, line) = (*self.lines.get(line.checked_sub(1)?)?)?;
+        Some((&self.files[file], line))
+    }
+
+    /// Replace line references of a driver compilation log, like
+    /// "0:12(5): error" (Mesa), "ERROR: 0:12:" (ANGLE) or "0(12) : error" (NVidia),
+    /// with the original file and line.
+    pub fn map_log(&self, log: &str) -> String {
+        log.lines()
+            .map(|line| self.map_log_line(line))
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    fn map_log_line(&self, line: &str) -> String {
+        let bytes = line.as_bytes();
+        for start in 0..bytes.len() {
+            if bytes[start] != b'0' || (start > 0 && bytes[start - 1].is_ascii_digit()) {
+                continue;
+            }
+            let close = match bytes.get(start + 1) {
+                Some(b':') => None,
+                Some(b'(') => Some(b')'),
+                _ => continue,
+            };
+            let digits_start = start + 2;
+            let digits_end = bytes[digits_start..]
+                .iter()
+                .position(|c| !c.is_ascii_digit())
+                .map_or(bytes.len(), |n| digits_start + n);
+            let end = match close {
+                Some(close) if bytes.get(digits_end) == Some(&close) => digits_end + 1,
+                Some(_) => continue,
+                None => digits_end,
+            };
+
+            let origin = line[digits_start..digits_end]
+                .parse()
+                .ok()
+                .and_then(|n| self.origin(n));
+            if let Some((file, n)) = origin {
+                return format!("{}{}:{}{}", &line[..start], file, n, &line[end..]);
+            }
+        }
+
+        line.to_string()
+    }
+}
+
+fn rewrite_identifiers<F>(line: &str, mut rewrite: F) -> String
+where
+    F: FnMut(&str) -> Option<&'static str>,
+{
+    let mut res = String::with_capacity(line.len());
+    let mut rest = line;
+
+    while let Some(start) = rest.find(|c: char| c.is_ascii_alphanumeric() || c == '_') {
+        let end = rest[start..]
+            .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+            .map_or(rest.len(), |n| start + n);
+        let word = &rest[start..end];
+        res.push_str(&rest[..start]);
+        res.push_str(rewrite(word).unwrap_or(word));
+        rest = &rest[end..];
+    }
+    res.push_str(rest);
+
+    res
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn process(version: GlslVersion, shader_type: ShaderType, source: &str) -> String {
+        Preprocessor::with_version(version)
+            .include_resolver(|path| match path {
+                "common.glsl" => Some("#pragma once\nfloat common() { return 1.0; }".to_string()),
+                _ => None,
+            })
+            .process(shader_type, "main.glsl", source)
+            .unwrap()
+            .source
+    }
+
+    #[test]
+    fn glsl_version_from_gl_version() {
+        let cases = [
+            ("OpenGL ES 3.2 Mesa 22.3.6", GlslVersion::Glsl300Es),
+            ("OpenGL ES 2.0 (ANGLE 2.1)", GlslVersion::Glsl100),
+            ("OpenGL ES-CM 1.1", GlslVersion::Glsl100),
+            ("4.6.0 NVIDIA 535.54.03", GlslVersion::Glsl330),
+            ("3.3 (Core Profile) Mesa 22.3.6", GlslVersion::Glsl330),
+            ("3.2.0", GlslVersion::Glsl150),
+            ("3.1 Mesa 22.3.6", GlslVersion::Glsl140),
+            ("3.0 Mesa 22.3.6", GlslVersion::Glsl130),
+            ("2.1 Metal - 76.3", GlslVersion::Glsl100),
+            ("", GlslVersion::Glsl100),
+        ];
+        for (version, glsl) in &cases {
+            assert_eq!(GlslVersion::from_gl_version(version), *glsl, "{}", version);
+        }
+    }
+
+    #[test]
+    fn rewrite_identifiers_whole_words() {
+        let rewrite = |word: &str| match word {
+            "varying" => Some("in"),
+            "texture2D" => Some("texture"),
+            _ => None,
+        };
+        assert_eq!(
+            rewrite_identifiers("varying vec2 uv;", rewrite),
+            "in vec2 uv;"
+        );
+        assert_eq!(
+            rewrite_identifiers("c = texture2D(tex, uv) + my_texture2D(uv);", rewrite),
+            "c = texture(tex, uv) + my_texture2D(uv);"
+        );
+        assert_eq!(rewrite_identifiers("  ", rewrite), "  ");
+    }
+
+    #[test]
+    fn version_header_and_defines() {
+        let source =
+            "#version 100\n#extension GL_OES_standard_derivatives : enable\nvoid main() {}";
+        let pre = Preprocessor::with_version(GlslVersion::Glsl100).define("SHADOWS", "1");
+        assert_eq!(
+            pre.process(ShaderType::Fragment, "main.glsl", source)
+                .unwrap()
+                .source,
+            "#version 100\n\
+             #extension GL_OES_standard_derivatives : enable\n\
+             precision mediump float;\n\
+             #define SHADOWS 1\n\
+             void main() {}\n"
+        );
+
+        let pre = Preprocessor::with_version(GlslVersion::Glsl330).define("SHADOWS", "1");
+        assert_eq!(
+            pre.process(ShaderType::Vertex, "main.glsl", source)
+                .unwrap()
+                .source,
+            "#version 330\n#define SHADOWS 1\nvoid main() {}\n"
+        );
+    }
+
+    #[test]
+    fn modern_targets() {
+        let vertex = "attribute vec2 pos;\nvarying vec2 uv;";
+        assert_eq!(
+            process(GlslVersion::Glsl300Es, ShaderType::Vertex, vertex),
+            "#version 300 es\nin vec2 pos;\nout vec2 uv;\n"
+        );
+        assert_eq!(
+            process(GlslVersion::Glsl100, ShaderType::Vertex, vertex),
+            "#version 100\nattribute vec2 pos;\nvarying vec2 uv;\n"
+        );
+
+        let fragment = "varying vec2 uv;\ngl_FragColor = texture2D(tex, uv);";
+        assert_eq!(
+            process(GlslVersion::Glsl330, ShaderType::Fragment, fragment),
+            "#version 330\n\
+             out vec4 miniquad_FragColor;\n\
+             in vec2 uv;\n\
+             miniquad_FragColor = texture(tex, uv);\n"
+        );
+        assert_eq!(
+            process(GlslVersion::Glsl150, ShaderType::Fragment, fragment),
+            "#version 150\n\
+             out vec4 miniquad_FragColor;\n\
+             in vec2 uv;\n\
+             miniquad_FragColor = texture(tex, uv);\n"
+        );
+    }
+
+    #[test]
+    fn frag_data() {
+        let fragment = "#extension GL_EXT_draw_buffers : require\n\
+                        gl_FragData[0] = a;\n\
+                        gl_FragData[1] = b;";
+        assert_eq!(
+            process(GlslVersion::Glsl300Es, ShaderType::Fragment, fragment),
+            "#version 300 es\n\
+             precision highp float;\n\
+             out vec4 miniquad_FragData[gl_MaxDrawBuffers];\n\
+             miniquad_FragData[0] = a;\n\
+             miniquad_FragData[1] = b;\n"
+        );
+        assert_eq!(
+            process(GlslVersion::Glsl100, ShaderType::Fragment, fragment),
+            "#version 100\n\
+             #extension GL_EXT_draw_buffers : require\n\
+             precision mediump float;\n\
+             gl_FragData[0] = a;\n\
+             gl_FragData[1] = b;\n"
+        );
+    }
+
+    #[test]
+    fn include_once() {
+        let source = "#include \"common.glsl\"\n#include <common.glsl>\nvoid main() {}";
+        assert_eq!(
+            process(GlslVersion::Glsl100, ShaderType::Vertex, source),
+            "#version 100\nfloat common() { return 1.0; }\nvoid main() {}\n"
+        );
+    }
+
+    #[test]
+    fn include_errors() {
+        let pre =
+            Preprocessor::with_version(GlslVersion::Glsl100).include_resolver(|path| match path {
+                "cycle.glsl" => Some("#include \"cycle.glsl\"".to_string()),
+                _ => None,
+            });
+        let error = |source: &str| match pre.process(ShaderType::Vertex, "main.glsl", source) {
+            Err(ShaderError::PreprocessError {
+                file,
+                line,
+                message,
+            }) => (file, line, message),
+            _ => panic!("{:?} should not preprocess", source),
+        };
+
+        assert_eq!(
+            error("\n#include \"missing.glsl\""),
+            (
+                "main.glsl".to_string(),
+                2,
+                "can't include \"missing.glsl\"".to_string()
+            )
+        );
+        assert_eq!(
+            error("#include missing.glsl"),
+            (
+                "main.glsl".to_string(),
+                1,
+                "malformed include: missing.glsl".to_string()
+            )
+        );
+        assert_eq!(
+            error("#include \"cycle.glsl\""),
+            (
+                "cycle.glsl".to_string(),
+                1,
+                "recursive include of \"cycle.glsl\"".to_string()
+            )
+        );
+    }
+
+    #[test]
+    fn map_log_lines() {
+        let source = Preprocessor::with_version(GlslVersion::Glsl100)
+            .include_resolver(|_| Some("float common() { return 1.0; }".to_string()))
+            .define("SHADOWS", "1")
+            .process(
+                ShaderType::Fragment,
+                "main.glsl",
+                "#include \"common.glsl\"\nvoid main() {}",
+            )
+            .unwrap();
+        // 1: version, 2: precision, 3: define, 4: common.glsl:1, 5: main.glsl:2
+        let cases = [
+            ("0:4(5): error: x", "common.glsl:1(5): error: x"),
+            (
+                "ERROR: 0:5: 'x' : undeclared",
+                "ERROR: main.glsl:2: 'x' : undeclared",
+            ),
+            ("0(5) : error C0000: x", "main.glsl:2 : error C0000: x"),
+            (
+                "0:2(1): error in the generated code",
+                "0:2(1): error in the generated code",
+            ),
+            ("0:99(1): error past the end", "0:99(1): error past the end"),
+            ("10:4: no line reference", "10:4: no line reference"),
+        ];
+        for (line, mapped) in &cases {
+            assert_eq!(source.map_log_line(line), *mapped);
+        }
+        assert_eq!(
+            source.map_log("0:4(5): a\n0:5(1): b"),
+            "common.glsl:1(5): a\nmain.glsl:2(1): b"
+        );
+    }
+}
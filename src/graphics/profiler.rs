@@ -0,0 +1,334 @@
+//! Per-frame tree of GPU timings, built on timestamp queries.
+
+use std::collections::VecDeque;
+
+use crate::Context;
+
+use super::{Query, QueryType};
+
+/// Frames not read back with `poll` after this many are dropped,
+/// so an application never polling does not pile up queries.
+const MAX_PENDING_FRAMES: usize = 8;
+
+/// GPU timing of a `GpuProfiler` scope.
+#[derive(Clone, Debug)]
+pub struct ProfilerScope {
+    pub name: String,
+    /// Nanoseconds from the frame start to `begin_scope`.
+    pub start: u64,
+    /// Nanoseconds from `begin_scope` to `end_scope`.
+    pub duration: u64,
+    /// Scopes begun and ended inside this one.
+    pub children: Vec<ProfilerScope>,
+}
+
+/// GPU timings of a frame recorded by `GpuProfiler`.
+#[derive(Clone, Debug)]
+pub struct ProfilerFrame {
+    /// Counts `begin_frame` calls, frames dropped or lost to a disjoint operation
+    /// leave gaps.
+    pub index: u64,
+    /// Nanoseconds from `begin_frame` to `end_frame`.
+    pub duration: u64,
+    /// Top level scopes, in the order they were begun.
+    pub scopes: Vec<ProfilerScope>,
+}
+
+struct PendingScope {
+    name: String,
+    parent: Option<usize>,
+    begin: Query,
+    end: Option<Query>,
+}
+
+/// `PendingScope` with its queries read back.
+struct ScopeTiming<'a> {
+    name: &'a str,
+    parent: Option<usize>,
+    begin: u64,
+    end: u64,
+}
+
+struct PendingFrame {
+    index: u64,
+    begin: Query,
+    end: Option<Query>,
+    scopes: Vec<PendingScope>,
+}
+
+/// Named, nested GPU timing scopes read back a few frames later without stalling.
+///
+/// Every scope boundary is a `QueryType::Timestamp` query, so scopes nest freely, unlike
+/// `QueryType::TimeElapsed` queries. Without `features.timestamp_query` the profiler records
+/// nothing and `poll` always returns None.
+///
+/// ```ignore
+/// profiler.begin_frame(ctx);
+/// profiler.begin_scope(ctx, "shadows");
+/// // draw calls
+/// profiler.end_scope(ctx);
+/// profiler.begin_scope(ctx, "scene");
+/// profiler.begin_scope(ctx, "opaque");
+/// // draw calls
+/// profiler.end_scope(ctx);
+/// profiler.end_scope(ctx);
+/// profiler.end_frame(ctx);
+///
+/// while let Some(frame) = profiler.poll(ctx) {
+///     println!("frame {}: {}us", frame.index, frame.duration / 1000);
+/// }
+/// ```
+pub struct GpuProfiler {
+    supported: bool,
+    frame_index: u64,
+    recording: Option<PendingFrame>,
+    /// Open scopes of the recording frame, innermost last.
+    stack: Vec<usize>,
+    pending: VecDeque<PendingFrame>,
+    /// Queries of read back or dropped frames, reused for the next ones.
+    free: Vec<Query>,
+}
+
+impl GpuProfiler {
+    pub fn new(ctx: &mut Context) -> GpuProfiler {
+        GpuProfiler {
+            supported: ctx.features.timestamp_query,
+            frame_index: 0,
+            recording: None,
+            stack: vec![],
+            pending: VecDeque::new(),
+            free: vec![],
+        }
+    }
+
+    fn timestamp(&mut self, ctx: &mut Context) -> Query {
+        let query = self
+            .free
+            .pop()
+            .unwrap_or_else(|| Query::new(ctx, QueryType::Timestamp));
+        query.timestamp();
+        query
+    }
+
+    fn recycle(&mut self, frame: PendingFrame) {
+        self.free.push(frame.begin);
+        self.free.extend(frame.end);
+        for scope in frame.scopes {
+            self.free.push(scope.begin);
+            self.free.extend(scope.end);
+        }
+    }
+
+    pub fn begin_frame(&mut self, ctx: &mut Context) {
+        assert!(self.recording.is_none(), "end_frame missing");
+        self.frame_index += 1;
+        if !self.supported {
+            return;
+        }
+
+        let begin = self.timestamp(ctx);
+        self.recording = Some(PendingFrame {
+            index: self.frame_index,
+            begin,
+            end: None,
+            scopes: vec![],
+        });
+    }
+
+    pub fn begin_scope(&mut self, ctx: &mut Context, name: &str) {
+        if !self.supported {
+            return;
+        }
+
+        let begin = self.timestamp(ctx);
+        let frame = self.recording.as_mut().expect("begin_frame missing");
+        frame.scopes.push(PendingScope {
+            name: name.to_string(),
+            parent: self.stack.last().copied(),
+            begin,
+            end: None,
+        });
+        self.stack.push(frame.scopes.len() - 1);
+    }
+
+    pub fn end_scope(&mut self, ctx: &mut Context) {
+        if !self.supported {
+            return;
+        }
+
+        let scope = self.stack.pop().expect("end_scope without begin_scope");
+        let end = self.timestamp(ctx);
+        self.recording.as_mut().unwrap().scopes[scope].end = Some(end);
+    }
+
+    pub fn end_frame(&mut self, ctx: &mut Context) {
+        if !self.supported {
+            return;
+        }
+
+        if let Some(&scope) = self.stack.last() {
+            panic!(
+                "end_scope missing for \"{}\"",
+                self.recording.as_ref().unwrap().scopes[scope].name
+            );
+        }
+        let end = self.timestamp(ctx);
+        let mut frame = self.recording.take().expect("begin_frame missing");
+        frame.end = Some(end);
+        self.pending.push_back(frame);
+
+        if self.pending.len() > MAX_PENDING_FRAMES {
+            let dropped = self.pending.pop_front().unwrap();
+            self.recycle(dropped);
+        }
+    }
+
+    /// The oldest recorded frame with all its results available, None if the GPU is not
+    /// done with it yet. Never waits for the GPU.
+    ///
+    /// On a `Context::gpu_disjoint` operation all the frames in flight are dropped.
+    pub fn poll(&mut self, ctx: &mut Context) -> Option<ProfilerFrame> {
+        if !self.supported {
+            return None;
+        }
+
+        if ctx.gpu_disjoint() {
+            while let Some(frame) = self.pending.pop_front() {
+                self.recycle(frame);
+            }
+            return None;
+        }
+
+        let available = {
+            let frame = self.pending.front()?;
+            frame.end.unwrap().is_available()
+                && frame
+                    .scopes
+                    .iter()
+                    .all(|scope| scope.end.unwrap().is_available())
+        };
+        if !available {
+            return None;
+        }
+
+        let frame = self.pending.pop_front().unwrap();
+        let start = frame.begin.get_result();
+        let timings: Vec<_> = frame
+            .scopes
+            .iter()
+            .map(|scope| ScopeTiming {
+                name: &scope.name,
+                parent: scope.parent,
+                begin: scope.begin.get_result(),
+                end: scope.end.unwrap().get_result(),
+            })
+            .collect();
+        let result = ProfilerFrame {
+            index: frame.index,
+            duration: frame.end.unwrap().get_result().saturating_sub(start),
+            scopes: scope_tree(&timings, None, start),
+        };
+        self.recycle(frame);
+        Some(result)
+    }
+
+    /// Delete all the queries. The profiler should not be used afterwards.
+    pub fn delete(&mut self) {
+        let frames: Vec<_> = self
+            .pending
+            .drain(..)
+            .chain(self.recording.take())
+            .collect();
+        for frame in frames {
+            self.recycle(frame);
+        }
+        for query in self.free.drain(..) {
+            query.delete();
+        }
+        self.stack.clear();
+    }
+}
+
+/// Children of `parent` with their subtrees, times relative to `frame_start`.
+fn scope_tree(
+    scopes: &[ScopeTiming],
+    parent: Option<usize>,
+    frame_start: u64,
+) -> Vec<ProfilerScope> {
+    scopes
+        .iter()
+        .enumerate()
+        .filter(|(_, scope)| scope.parent == parent)
+        .map(|(index, scope)| ProfilerScope {
+            name: scope.name.to_string(),
+            start: scope.begin.saturating_sub(frame_start),
+            duration: scope.end.saturating_sub(scope.begin),
+            children: scope_tree(scopes, Some(index), frame_start),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn timing(name: &str, parent: Option<usize>, begin: u64, end: u64) -> ScopeTiming<'_> {
+        ScopeTiming {
+            name,
+            parent,
+            begin,
+            end,
+        }
+    }
+
+    /// (depth, name, start, duration) of the scopes, depth first.
+    fn flatten(scopes: &[ProfilerScope], depth: usize, res: &mut Vec<(usize, String, u64, u64)>) {
+        for scope in scopes {
+            res.push((depth, scope.name.clone(), scope.start, scope.duration));
+            flatten(&scope.children, depth + 1, res);
+        }
+    }
+
+    #[test]
+    fn nested_scopes() {
+        // shadows, scene { opaque, transparent { particles } }, ui
+        let timings = [
+            timing("shadows", None, 1010, 1100),
+            timing("scene", None, 1100, 1500),
+            timing("opaque", Some(1), 1110, 1300),
+            timing("transparent", Some(1), 1300, 1490),
+            timing("particles", Some(3), 1400, 1450),
+            timing("ui", None, 1500, 1520),
+        ];
+        let mut flat = vec![];
+        flatten(&scope_tree(&timings, None, 1000), 0, &mut flat);
+
+        let expected = [
+            (0, "shadows", 10, 90),
+            (0, "scene", 100, 400),
+            (1, "opaque", 110, 190),
+            (1, "transparent", 300, 190),
+            (2, "particles", 400, 50),
+            (0, "ui", 500, 20),
+        ];
+        let expected: Vec<_> = expected
+            .iter()
+            .map(|(depth, name, start, duration)| (*depth, name.to_string(), *start, *duration))
+            .collect();
+        assert_eq!(flat, expected);
+    }
+
+    #[test]
+    fn out_of_order_timestamps_saturate() {
+        // timestamps of a disjoint frame may go backwards
+        let timings = [timing("scope", None, 900, 800)];
+        let tree = scope_tree(&timings, None, 1000);
+        assert_eq!((tree[0].start, tree[0].duration), (0, 0));
+        assert!(tree[0].children.is_empty());
+    }
+
+    #[test]
+    fn no_scopes() {
+        assert!(scope_tree(&[], None, 0).is_empty());
+    }
+}
@@ -0,0 +1,113 @@
+//! On-disk cache of linked program binaries, see `conf::Conf::program_cache`.
+
+use std::path::PathBuf;
+
+use crate::native::gl::*;
+
+use super::gl_string;
+
+/// Entries are `<dir>/<key>.bin` files: the binary format as a little endian u32,
+/// followed by the driver blob.
+pub(crate) struct ProgramCache {
+    dir: PathBuf,
+    /// Blobs are valid only for the exact driver that produced them,
+    /// so vendor, renderer and version strings are a part of the key.
+    driver: String,
+}
+
+/// FNV-1a, unlike `DefaultHasher` it is guaranteed to give the same keys
+/// across rust versions.
+fn fnv1a(mut hash: u64, bytes: &[u8]) -> u64 {
+    for byte in bytes {
+        hash ^= *byte as u64;
+        hash = hash.wrapping_mul(0x100000001b3);
+    }
+    hash
+}
+
+impl ProgramCache {
+    pub(crate) unsafe fn new(dir: PathBuf) -> ProgramCache {
+        let driver = format!(
+            "{}\n{}\n{}",
+            gl_string(GL_VENDOR),
+            gl_string(GL_RENDERER),
+            gl_string(GL_VERSION)
+        );
+        ProgramCache { dir, driver }
+    }
+
+    fn path(&self, vertex_shader: &str, fragment_shader: &str) -> PathBuf {
+        let mut hash = 0xcbf29ce484222325;
+        for part in &[&self.driver, vertex_shader, fragment_shader] {
+            hash = fnv1a(hash, part.as_bytes());
+            // so moving code from one shader to the other changes the key
+            hash = fnv1a(hash, &[0]);
+        }
+        self.dir.join(format!("{:016x}.bin", hash))
+    }
+
+    /// Linked program created from the cached binary.
+    /// None if there is no entry or the driver rejected it.
+    pub(crate) unsafe fn load(&self, vertex_shader: &str, fragment_shader: &str) -> Option<GLuint> {
+        let data = std::fs::read(self.path(vertex_shader, fragment_shader)).ok()?;
+        if data.len() <= 4 {
+            return None;
+        }
+        let format = u32::from_le_bytes([data[0], data[1], data[2], data[3]]);
+
+        let program = glCreateProgram();
+        glProgramBinary(
+            program,
+            format,
+            data[4..].as_ptr() as *const _,
+            (data.len() - 4) as _,
+        );
+
+        let mut link_status = 0;
+        glGetProgramiv(program, GL_LINK_STATUS, &mut link_status as *mut _);
+        if link_status == 0 {
+            // driver update or a corrupted file, the program will be compiled
+            // from the sources and the entry overwritten by `store`
+            glDeleteProgram(program);
+            return None;
+        }
+        Some(program)
+    }
+
+    /// Should be called before glLinkProgram, otherwise the driver
+    /// is free to drop the binary after linking.
+    pub(crate) unsafe fn prepare(&self, program: GLuint) {
+        glProgramParameteri(program, GL_PROGRAM_BINARY_RETRIEVABLE_HINT, GL_TRUE as _);
+    }
+
+    pub(crate) unsafe fn store(&self, vertex_shader: &str, fragment_shader: &str, program: GLuint) {
+        let mut length = 0;
+        glGetProgramiv(program, GL_PROGRAM_BINARY_LENGTH, &mut length as *mut _);
+        if length <= 0 {
+            return;
+        }
+
+        let mut data = vec![0u8; 4 + length as usize];
+        let mut format: GLenum = 0;
+        glGetProgramBinary(
+            program,
+            length,
+            &mut length as *mut _,
+            &mut format as *mut _,
+            data[4..].as_mut_ptr() as *mut _,
+        );
+        data.truncate(4 + length.max(0) as usize);
+        data[..4].copy_from_slice(&format.to_le_bytes());
+
+        // the cache is an optimization, failing to write it is not an error.
+        // Entries are renamed into place, so another process never reads a half-written file.
+        let path = self.path(vertex_shader, fragment_shader);
+        let tmp = path.with_extension(format!("{}.tmp", std::process::id()));
+        let res = std::fs::create_dir_all(&self.dir)
+            .and_then(|_| std::fs::write(&tmp, &data))
+            .and_then(|_| std::fs::rename(&tmp, &path));
+        if res.is_err() {
+            let _ = std::fs::remove_file(&tmp);
+        }
+    }
+}
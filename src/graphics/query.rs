@@ -0,0 +1,171 @@
+//! GPU queries, results are read back asynchronously a few frames later.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use crate::{native::gl::*, Context};
+
+#[cfg(target_os = "ios")]
+use crate::native::query_stab::{glGetQueryObjectui64v, glQueryCounter};
+
+use super::Features;
+
+/// `Features::timer_query` of the last created context, for `ElapsedQuery::is_supported`
+/// that has no context to look at.
+pub(crate) static TIMER_QUERY: AtomicBool = AtomicBool::new(false);
+
+pub(crate) fn set_timer_query_supported(supported: bool) {
+    TIMER_QUERY.store(supported, Ordering::Relaxed);
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum QueryType {
+    /// 1 if any sample passed the depth and stencil tests between `begin_query`
+    /// and `end_query`, 0 otherwise. Requires `features.occlusion_query`.
+    AnySamplesPassed,
+    /// GPU time between `begin_query` and `end_query`, in nanoseconds.
+    /// Requires `features.timer_query`.
+    TimeElapsed,
+    /// GPU time when all the commands before `Query::timestamp` are done, in nanoseconds.
+    /// Only the difference of two timestamps is meaningful.
+    /// Requires `features.timestamp_query`.
+    Timestamp,
+    /// Vertices fetched by draw calls. Requires `features.pipeline_statistics_query`.
+    VerticesSubmitted,
+    /// Primitives assembled by draw calls. Requires `features.pipeline_statistics_query`.
+    PrimitivesSubmitted,
+    /// Requires `features.pipeline_statistics_query`.
+    VertexShaderInvocations,
+    /// Requires `features.pipeline_statistics_query`.
+    FragmentShaderInvocations,
+    /// Primitives reaching the clipping stage. Requires `features.pipeline_statistics_query`.
+    ClippingInputPrimitives,
+    /// Primitives left after clipping. Requires `features.pipeline_statistics_query`.
+    ClippingOutputPrimitives,
+}
+
+impl QueryType {
+    fn gl_target(&self) -> GLenum {
+        match self {
+            QueryType::AnySamplesPassed => GL_ANY_SAMPLES_PASSED,
+            QueryType::TimeElapsed => GL_TIME_ELAPSED,
+            QueryType::Timestamp => GL_TIMESTAMP,
+            QueryType::VerticesSubmitted => GL_VERTICES_SUBMITTED,
+            QueryType::PrimitivesSubmitted => GL_PRIMITIVES_SUBMITTED,
+            QueryType::VertexShaderInvocations => GL_VERTEX_SHADER_INVOCATIONS,
+            QueryType::FragmentShaderInvocations => GL_FRAGMENT_SHADER_INVOCATIONS,
+            QueryType::ClippingInputPrimitives => GL_CLIPPING_INPUT_PRIMITIVES,
+            QueryType::ClippingOutputPrimitives => GL_CLIPPING_OUTPUT_PRIMITIVES,
+        }
+    }
+
+    pub(crate) fn is_supported(&self, features: &Features) -> bool {
+        match self {
+            QueryType::AnySamplesPassed => features.occlusion_query,
+            QueryType::TimeElapsed => features.timer_query,
+            QueryType::Timestamp => features.timestamp_query,
+            _ => features.pipeline_statistics_query,
+        }
+    }
+
+    /// Occlusion results are read as 32 bit, the 64 bit getter comes with timer queries.
+    fn is_64bit(&self) -> bool {
+        *self != QueryType::AnySamplesPassed
+    }
+}
+
+/// A GL query object. Unlike `ElapsedQuery` the query types are checked against `Features`
+/// and any number of queries of different types may be active at once,
+/// but only one per type.
+///
+/// ```ignore
+/// let query = Query::new(ctx, QueryType::AnySamplesPassed);
+/// query.begin_query();
+/// ctx.draw(0, 36, 1); // bounding box
+/// query.end_query();
+///
+/// // a frame or two later
+/// if query.is_available() {
+///     let visible = query.get_result() != 0;
+/// }
+/// ```
+#[derive(Clone, Copy, Debug)]
+pub struct Query {
+    gl_query: GLuint,
+    query_type: QueryType,
+}
+
+impl Query {
+    pub fn new(ctx: &mut Context, query_type: QueryType) -> Query {
+        assert!(
+            query_type.is_supported(&ctx.features),
+            "{:?} queries are not supported by the context",
+            query_type
+        );
+
+        let mut gl_query = 0;
+        unsafe { glGenQueries(1, &mut gl_query as *mut _) };
+        Query {
+            gl_query,
+            query_type,
+        }
+    }
+
+    pub fn query_type(&self) -> QueryType {
+        self.query_type
+    }
+
+    /// Not for `QueryType::Timestamp`, use `timestamp` instead.
+    pub fn begin_query(&self) {
+        assert!(self.query_type != QueryType::Timestamp);
+        unsafe { glBeginQuery(self.query_type.gl_target(), self.gl_query) };
+    }
+
+    pub fn end_query(&self) {
+        assert!(self.query_type != QueryType::Timestamp);
+        unsafe { glEndQuery(self.query_type.gl_target()) };
+    }
+
+    /// Record the GPU time once all the commands submitted so far are done.
+    /// Only for `QueryType::Timestamp`.
+    pub fn timestamp(&self) {
+        assert!(self.query_type == QueryType::Timestamp);
+        unsafe { glQueryCounter(self.gl_query, GL_TIMESTAMP) };
+    }
+
+    /// Whether `get_result` returns without waiting for the GPU.
+    pub fn is_available(&self) -> bool {
+        let mut available: GLuint = 0;
+        unsafe {
+            glGetQueryObjectuiv(
+                self.gl_query,
+                GL_QUERY_RESULT_AVAILABLE,
+                &mut available as *mut _,
+            )
+        };
+        available != 0
+    }
+
+    /// Blocks until the GPU is done with the query if it is not `is_available` yet.
+    ///
+    /// Timer results on gles and webgl are garbage when `Context::gpu_disjoint` reports
+    /// a disjoint operation since the queries were issued.
+    pub fn get_result(&self) -> u64 {
+        unsafe {
+            if self.query_type.is_64bit() {
+                let mut result: GLuint64 = 0;
+                glGetQueryObjectui64v(self.gl_query, GL_QUERY_RESULT, &mut result as *mut _);
+                result
+            } else {
+                // the signed variant is gles only through EXT_disjoint_timer_query
+                let mut result: GLuint = 0;
+                glGetQueryObjectuiv(self.gl_query, GL_QUERY_RESULT, &mut result as *mut _);
+                result as u64
+            }
+        }
+    }
+
+    /// Delete GL query object, leaving handle unmodified.
+    pub fn delete(&self) {
+        unsafe { glDeleteQueries(1, &self.gl_query as *const _) }
+    }
+}
@@ -0,0 +1,142 @@
+//! Buffer state kept outside of `GraphicsContext`, for the `Buffer` methods that have
+//! no context to look at: what is kept to recreate resources after a context loss, see
+//! `GraphicsContext::enable_resource_registry`, and the current size and CPU copy of
+//! resizable buffers.
+
+use std::{
+    cell::RefCell,
+    collections::HashMap,
+    sync::atomic::{AtomicU32, Ordering},
+};
+
+use crate::native::gl::*;
+
+use super::{gl_buffer_target, BufferType};
+
+pub(crate) struct ResourceRegistry {
+    /// Immutable buffers are registered with a CPU copy of their data.
+    pub keep_buffer_data: bool,
+}
+
+struct BufferRecord {
+    /// Current GL name, a new one after every restoration.
+    gl_buf: GLuint,
+    buffer_type: BufferType,
+    data: Vec<u8>,
+}
+
+thread_local! {
+    /// Registered buffers by `Buffer` registry id, ids start at 1.
+    /// Kept outside of the context for `Buffer::delete`, which has no context to look at.
+    static BUFFERS: RefCell<HashMap<u32, BufferRecord>> = RefCell::new(HashMap::new());
+    /// Size of `stream` and `dynamic` buffers by GL name. `Buffer::resize` changes it for
+    /// every copy of the handle.
+    static BUFFER_SIZES: RefCell<HashMap<GLuint, usize>> = RefCell::new(HashMap::new());
+    /// CPU copies of the `stream` and `dynamic` buffers for `Buffer::resize` by GL name,
+    /// only without `features.copy_buffer`.
+    static BUFFER_SHADOWS: RefCell<HashMap<GLuint, Vec<u8>>> = RefCell::new(HashMap::new());
+    /// Storage of the `RingBuffer`s by GL name, true for the immutable persistently
+    /// mapped one. `Buffer::resize` and orphaning `Buffer::update_range` look here.
+    static RING_BUFFERS: RefCell<HashMap<GLuint, bool>> = RefCell::new(HashMap::new());
+}
+
+static NEXT_BUFFER_ID: AtomicU32 = AtomicU32::new(1);
+
+pub(crate) fn register_buffer(gl_buf: GLuint, buffer_type: BufferType, data: Vec<u8>) -> u32 {
+    let id = NEXT_BUFFER_ID.fetch_add(1, Ordering::Relaxed);
+    BUFFERS.with(|buffers| {
+        buffers.borrow_mut().insert(
+            id,
+            BufferRecord {
+                gl_buf,
+                buffer_type,
+                data,
+            },
+        )
+    });
+    id
+}
+
+/// Current GL name of a registered buffer.
+pub(crate) fn buffer_name(id: u32) -> Option<GLuint> {
+    BUFFERS.with(|buffers| buffers.borrow().get(&id).map(|record| record.gl_buf))
+}
+
+/// Forget a deleted buffer, returning its current GL name.
+pub(crate) fn unregister_buffer(id: u32) -> Option<GLuint> {
+    BUFFERS.with(|buffers| buffers.borrow_mut().remove(&id).map(|record| record.gl_buf))
+}
+
+/// Upload every registered buffer into a new GL buffer of the current context.
+/// Leaves nothing bound, as a freshly created context expects.
+pub(crate) unsafe fn restore_buffers() {
+    BUFFERS.with(|buffers| {
+        for record in buffers.borrow_mut().values_mut() {
+            let gl_target = gl_buffer_target(&record.buffer_type);
+            glGenBuffers(1, &mut record.gl_buf as *mut _);
+            glBindBuffer(gl_target, record.gl_buf);
+            glBufferData(
+                gl_target,
+                record.data.len() as _,
+                record.data.as_ptr() as *const _,
+                GL_STATIC_DRAW,
+            );
+            glBindBuffer(gl_target, 0);
+        }
+    });
+}
+
+/// Track a new resizable buffer. GL names are reused, so this replaces whatever was
+/// left from a deleted buffer.
+pub(crate) fn track_buffer(gl_buf: GLuint, size: usize, shadow: Option<Vec<u8>>) {
+    BUFFER_SIZES.with(|sizes| sizes.borrow_mut().insert(gl_buf, size));
+    BUFFER_SHADOWS.with(|shadows| match shadow {
+        Some(shadow) => shadows.borrow_mut().insert(gl_buf, shadow),
+        None => shadows.borrow_mut().remove(&gl_buf),
+    });
+    RING_BUFFERS.with(|rings| rings.borrow_mut().remove(&gl_buf));
+}
+
+/// Mark the buffer of a `RingBuffer`, after `track_buffer` if it has one.
+pub(crate) fn track_ring_buffer(gl_buf: GLuint, persistent: bool) {
+    RING_BUFFERS.with(|rings| rings.borrow_mut().insert(gl_buf, persistent));
+}
+
+/// Some(persistent) for the buffer of a `RingBuffer`.
+pub(crate) fn ring_buffer(gl_buf: GLuint) -> Option<bool> {
+    RING_BUFFERS.with(|rings| rings.borrow().get(&gl_buf).copied())
+}
+
+/// Forget a deleted buffer, or one of a lost context.
+pub(crate) fn untrack_buffer(gl_buf: GLuint) {
+    BUFFER_SIZES.with(|sizes| sizes.borrow_mut().remove(&gl_buf));
+    BUFFER_SHADOWS.with(|shadows| shadows.borrow_mut().remove(&gl_buf));
+    RING_BUFFERS.with(|rings| rings.borrow_mut().remove(&gl_buf));
+}
+
+/// Every buffer of a lost context is gone.
+pub(crate) fn untrack_buffers() {
+    BUFFER_SIZES.with(|sizes| sizes.borrow_mut().clear());
+    BUFFER_SHADOWS.with(|shadows| shadows.borrow_mut().clear());
+    RING_BUFFERS.with(|rings| rings.borrow_mut().clear());
+}
+
+pub(crate) fn buffer_size(gl_buf: GLuint) -> Option<usize> {
+    BUFFER_SIZES.with(|sizes| sizes.borrow().get(&gl_buf).copied())
+}
+
+pub(crate) fn set_buffer_size(gl_buf: GLuint, size: usize) {
+    BUFFER_SIZES.with(|sizes| sizes.borrow_mut().insert(gl_buf, size));
+}
+
+pub(crate) fn take_buffer_shadow(gl_buf: GLuint) -> Option<Vec<u8>> {
+    BUFFER_SHADOWS.with(|shadows| shadows.borrow_mut().remove(&gl_buf))
+}
+
+/// Run `f` on the CPU copy of the buffer, None if it has none.
+pub(crate) fn with_buffer_shadow<R>(
+    gl_buf: GLuint,
+    f: impl FnOnce(&mut Vec<u8>) -> R,
+) -> Option<R> {
+    BUFFER_SHADOWS.with(|shadows| shadows.borrow_mut().get_mut(&gl_buf).map(f))
+}
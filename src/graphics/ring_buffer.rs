@@ -0,0 +1,289 @@
+//! Streaming allocator for per-frame vertex and index data.
+
+use std::{mem, ptr};
+
+use crate::{native::gl::*, Context};
+
+use super::{gl_buffer_target, registry, Buffer, BufferType, IndexType, Usage};
+
+/// Persistently mapped storage is fenced in this many parts. The CPU waits for the GPU
+/// only when the write head comes back to a part the GPU may still be reading.
+const RING_SEGMENTS: usize = 4;
+
+/// Streams data of many small draw calls into one big `Buffer`.
+///
+/// The storage is written with the fastest path of the context:
+/// + mapped once and kept mapped, with fences against overwriting data the GPU still reads,
+///   if `features.persistent_mapping`;
+/// + unsynchronized glMapBufferRange of each push, orphaning the storage on wrap around,
+///   if `features.buffer_mapping`;
+/// + glBufferSubData of each push, orphaning the storage on wrap around, otherwise.
+///
+/// ```ignore
+/// let offset = ring.push(ctx, &vertices);
+/// ctx.apply_bindings(&Bindings {
+///     vertex_buffers: vec![ring.buffer()],
+///     ..
+/// });
+/// let base_vertex = offset / std::mem::size_of::<Vertex>();
+/// ctx.draw_base_vertex(0, indices.len() as i32, base_vertex as i32, 1);
+/// ```
+pub struct RingBuffer {
+    buffer: Buffer,
+    head: usize,
+    /// Persistent mapping of the whole storage, null for the other write paths.
+    mapped: *mut u8,
+    fences: [GLsync; RING_SEGMENTS],
+    /// Segment of the last push, the GPU may still be reading all the others.
+    segment: usize,
+}
+
+impl RingBuffer {
+    /// Index buffers are of `IndexType::Short`, same as `Buffer::stream`.
+    pub fn new(ctx: &mut Context, buffer_type: BufferType, size: usize) -> RingBuffer {
+        let index_type = if buffer_type == BufferType::IndexBuffer {
+            Some(IndexType::Short)
+        } else {
+            None
+        };
+
+        let mut mapped = ptr::null_mut();
+        let buffer = if ctx.features.persistent_mapping {
+            let gl_target = gl_buffer_target(&buffer_type);
+            let mut gl_buf = 0;
+            unsafe {
+                glGenBuffers(1, &mut gl_buf as *mut _);
+                ctx.cache.store_buffer_binding(gl_target);
+                ctx.cache.bind_buffer(gl_target, gl_buf, index_type);
+                mapped = map_persistent(gl_target, size);
+                ctx.cache.restore_buffer_binding(gl_target);
+            }
+            Buffer {
+                gl_buf,
+                buffer_type,
+                usage: Usage::Stream,
+                size,
+                index_type,
+                registry_id: 0,
+            }
+        } else {
+            Buffer::uninitialized(ctx, buffer_type, index_type, Usage::Stream, size, false)
+        };
+
+        registry::track_ring_buffer(buffer.gl_buf, !mapped.is_null());
+
+        RingBuffer {
+            buffer,
+            head: 0,
+            mapped,
+            fences: [ptr::null_mut(); RING_SEGMENTS],
+            segment: 0,
+        }
+    }
+
+    /// The buffer to use in `Bindings`.
+    pub fn buffer(&self) -> Buffer {
+        self.buffer
+    }
+
+    /// Copy `data` into the ring and return its offset in `buffer()`, in bytes.
+    /// The offset is a multiple of `size_of::<T>()`, so `offset / size_of::<T>()`
+    /// is the `base_vertex` or `base_element` of the data.
+    ///
+    /// Pushed data is kept until the ring wraps around onto it.
+    /// With persistent mapping the draw calls using it should be issued before another
+    /// quarter of the ring is pushed, later draw calls may read it already overwritten.
+    pub fn push<T>(&mut self, ctx: &mut Context, data: &[T]) -> usize {
+        if self.buffer.buffer_type == BufferType::IndexBuffer {
+            assert!(self.buffer.index_type == Some(IndexType::for_type::<T>()));
+        }
+
+        let size = mem::size_of_val(data);
+        assert!(
+            size <= self.buffer.size,
+            "Data does not fit in the RingBuffer"
+        );
+
+        let (offset, wrap) = place(self.head, self.buffer.size, size, mem::size_of::<T>());
+        if size == 0 {
+            return offset;
+        }
+        self.head = offset + size;
+
+        unsafe {
+            if !self.mapped.is_null() {
+                self.enter_segments(offset + size - 1, wrap);
+                ptr::copy_nonoverlapping(data.as_ptr() as *const u8, self.mapped.add(offset), size);
+                return offset;
+            }
+
+            let gl_target = gl_buffer_target(&self.buffer.buffer_type);
+            ctx.cache.store_buffer_binding(gl_target);
+            ctx.cache
+                .bind_buffer(gl_target, self.buffer.gl_buf, self.buffer.index_type);
+
+            // fresh storage, so nothing written from now on overlaps data of pending draw calls
+            if wrap {
+                glBufferData(
+                    gl_target,
+                    self.buffer.size as _,
+                    ptr::null(),
+                    GL_STREAM_DRAW,
+                );
+            }
+
+            if ctx.features.buffer_mapping {
+                let dst = glMapBufferRange(
+                    gl_target,
+                    offset as _,
+                    size as _,
+                    GL_MAP_WRITE_BIT | GL_MAP_UNSYNCHRONIZED_BIT | GL_MAP_INVALIDATE_RANGE_BIT,
+                );
+                ptr::copy_nonoverlapping(data.as_ptr() as *const u8, dst as *mut u8, size);
+                glUnmapBuffer(gl_target);
+            } else {
+                glBufferSubData(gl_target, offset as _, size as _, data.as_ptr() as *const _);
+            }
+
+            ctx.cache.restore_buffer_binding(gl_target);
+        }
+
+        offset
+    }
+
+    /// Move the write head to the segment with the `last_byte`, fencing the segments left
+    /// behind and waiting for the GPU to be done with the segments entered.
+    unsafe fn enter_segments(&mut self, last_byte: usize, wrap: bool) {
+        let steps = segment_steps(self.segment, last_byte, self.buffer.size, wrap);
+
+        for _ in 0..steps {
+            self.fences[self.segment] = glFenceSync(GL_SYNC_GPU_COMMANDS_COMPLETE, 0);
+            self.segment = (self.segment + 1) % RING_SEGMENTS;
+
+            let fence = mem::replace(&mut self.fences[self.segment], ptr::null_mut());
+            if !fence.is_null() {
+                wait_fence(fence);
+            }
+        }
+    }
+
+    pub fn delete(&mut self, ctx: &mut Context) {
+        unsafe {
+            if !self.mapped.is_null() {
+                let gl_target = gl_buffer_target(&self.buffer.buffer_type);
+                ctx.cache.store_buffer_binding(gl_target);
+                ctx.cache
+                    .bind_buffer(gl_target, self.buffer.gl_buf, self.buffer.index_type);
+                glUnmapBuffer(gl_target);
+                ctx.cache.restore_buffer_binding(gl_target);
+                self.mapped = ptr::null_mut();
+            }
+            for fence in &mut self.fences {
+                if !fence.is_null() {
+                    glDeleteSync(*fence);
+                    *fence = ptr::null_mut();
+                }
+            }
+        }
+        self.buffer.delete();
+    }
+}
+
+/// Offset of `size` bytes aligned to `align` at the write `head` of a ring of `capacity` bytes,
+/// and whether it had to wrap around to the start.
+fn place(head: usize, capacity: usize, size: usize, align: usize) -> (usize, bool) {
+    let align = align.max(1);
+    let offset = head + (align - head % align) % align;
+    if offset + size > capacity {
+        (0, true)
+    } else {
+        (offset, false)
+    }
+}
+
+/// Segments the write head moves through, from `segment` to the one with `last_byte`.
+fn segment_steps(segment: usize, last_byte: usize, capacity: usize, wrap: bool) -> usize {
+    let segment_size = (capacity / RING_SEGMENTS).max(1);
+    let last_segment = (last_byte / segment_size).min(RING_SEGMENTS - 1);
+
+    // wrapping around goes through the rest of the segments back to the first one
+    if wrap {
+        RING_SEGMENTS - segment + last_segment
+    } else {
+        last_segment - segment
+    }
+}
+
+unsafe fn wait_fence(fence: GLsync) {
+    loop {
+        let res = glClientWaitSync(fence, GL_SYNC_FLUSH_COMMANDS_BIT, 1_000_000_000);
+        if res == GL_ALREADY_SIGNALED || res == GL_CONDITION_SATISFIED || res == GL_WAIT_FAILED {
+            break;
+        }
+    }
+    glDeleteSync(fence);
+}
+
+/// Immutable storage for the buffer bound to `gl_target`, mapped until the buffer is deleted.
+#[cfg(not(any(target_os = "macos", target_os = "ios")))]
+unsafe fn map_persistent(gl_target: GLenum, size: usize) -> *mut u8 {
+    let flags = GL_MAP_WRITE_BIT | GL_MAP_PERSISTENT_BIT | GL_MAP_COHERENT_BIT;
+    glBufferStorage(gl_target, size as _, ptr::null(), flags);
+    let mapped = glMapBufferRange(gl_target, 0, size as _, flags);
+    assert!(!mapped.is_null(), "Failed to map RingBuffer storage");
+    mapped as *mut u8
+}
+
+// there is no glBufferStorage on apple, and so no `features.persistent_mapping`
+#[cfg(any(target_os = "macos", target_os = "ios"))]
+unsafe fn map_persistent(_gl_target: GLenum, _size: usize) -> *mut u8 {
+    unreachable!()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn place_aligns_the_head() {
+        assert_eq!(place(0, 64, 12, 4), (0, false));
+        assert_eq!(place(12, 64, 8, 8), (16, false));
+        assert_eq!(place(13, 64, 4, 4), (16, false));
+        // zero sized types are byte aligned
+        assert_eq!(place(13, 64, 0, 0), (13, false));
+    }
+
+    #[test]
+    fn place_wraps_when_data_does_not_fit() {
+        assert_eq!(place(48, 64, 16, 4), (48, false));
+        assert_eq!(place(52, 64, 16, 4), (0, true));
+        // padding for the alignment counts too
+        assert_eq!(place(57, 64, 4, 8), (0, true));
+        assert_eq!(place(64, 64, 1, 1), (0, true));
+    }
+
+    #[test]
+    fn segment_steps_forward() {
+        // 16 byte segments
+        assert_eq!(segment_steps(0, 15, 64, false), 0);
+        assert_eq!(segment_steps(0, 16, 64, false), 1);
+        assert_eq!(segment_steps(1, 63, 64, false), 2);
+        // the tail of a ring not divisible by the segment count is in the last segment
+        assert_eq!(segment_steps(0, 65, 66, false), 3);
+    }
+
+    #[test]
+    fn segment_steps_wrap_around() {
+        // back to the first segment through all the others
+        assert_eq!(segment_steps(3, 4, 64, true), 1);
+        assert_eq!(segment_steps(2, 20, 64, true), 3);
+        // a wrap from the first segment fences it and waits for it again
+        assert_eq!(segment_steps(0, 0, 64, true), 4);
+    }
+
+    #[test]
+    fn tiny_rings_have_one_byte_segments() {
+        assert_eq!(segment_steps(0, 2, 3, false), 2);
+        assert_eq!(segment_steps(2, 0, 3, true), 2);
+    }
+}
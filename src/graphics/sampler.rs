@@ -0,0 +1,174 @@
+use crate::{native::gl::*, Comparison, Context};
+
+use super::texture::min_filter;
+use super::{FilterMode, MipmapFilterMode, TextureWrap};
+
+/// Filtering, wrapping, LOD and comparison state used when sampling a texture.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SamplerParams {
+    pub min_filter: FilterMode,
+    pub mag_filter: FilterMode,
+    /// Makes sense only for textures with mipmaps.
+    pub mipmap_filter: MipmapFilterMode,
+    pub wrap_x: TextureWrap,
+    pub wrap_y: TextureWrap,
+    /// Used for 3D textures only.
+    pub wrap_z: TextureWrap,
+    /// Color outside of the texture with `TextureWrap::ClampToBorder`.
+    pub border_color: (f32, f32, f32, f32),
+    pub min_lod: f32,
+    pub max_lod: f32,
+    /// 1.0 disables anisotropic filtering.
+    /// Clamped to `Features::max_anisotropy`.
+    pub max_anisotropy: f32,
+    /// Depth comparison for shadow samplers (`sampler2DShadow` in GLSL).
+    /// None for regular sampling.
+    pub compare: Option<Comparison>,
+}
+
+impl Default for SamplerParams {
+    fn default() -> Self {
+        SamplerParams {
+            min_filter: FilterMode::Linear,
+            mag_filter: FilterMode::Linear,
+            mipmap_filter: MipmapFilterMode::None,
+            wrap_x: TextureWrap::Clamp,
+            wrap_y: TextureWrap::Clamp,
+            wrap_z: TextureWrap::Clamp,
+            border_color: (0., 0., 0., 0.),
+            min_lod: -1000.,
+            max_lod: 1000.,
+            max_anisotropy: 1.,
+            compare: None,
+        }
+    }
+}
+
+/// Sampling state separated from the texture, so one texture may be sampled
+/// in different ways. Sampler is applied to a texture with
+/// `Context::apply_bindings_with_samplers`.
+///
+/// gles2 and webgl1 have no sampler objects. There samplers are emulated by writing
+/// filters, wraps and anisotropy into the texture, and the texture's own state is
+/// written back once it is bound without a sampler. LOD clamps, border color and
+/// comparison are ignored.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Sampler {
+    pub(crate) sampler: GLuint,
+    pub params: SamplerParams,
+}
+
+impl Sampler {
+    pub fn new(ctx: &mut Context, params: SamplerParams) -> Sampler {
+        let mut sampler = 0;
+
+        if ctx.features.sampler_objects {
+            unsafe {
+                glGenSamplers(1, &mut sampler as *mut _);
+                glSamplerParameteri(
+                    sampler,
+                    GL_TEXTURE_MIN_FILTER,
+                    min_filter(params.min_filter, params.mipmap_filter) as i32,
+                );
+                glSamplerParameteri(sampler, GL_TEXTURE_MAG_FILTER, params.mag_filter as i32);
+                glSamplerParameteri(sampler, GL_TEXTURE_WRAP_S, params.wrap_x as i32);
+                glSamplerParameteri(sampler, GL_TEXTURE_WRAP_T, params.wrap_y as i32);
+                glSamplerParameteri(sampler, GL_TEXTURE_WRAP_R, params.wrap_z as i32);
+                glSamplerParameterf(sampler, GL_TEXTURE_MIN_LOD, params.min_lod);
+                glSamplerParameterf(sampler, GL_TEXTURE_MAX_LOD, params.max_lod);
+
+                // border color is gles3.2+, do not touch it unless asked for
+                let (r, g, b, a) = params.border_color;
+                if params.border_color != (0., 0., 0., 0.) {
+                    glSamplerParameterfv(sampler, GL_TEXTURE_BORDER_COLOR, [r, g, b, a].as_ptr());
+                }
+                if ctx.features.max_anisotropy > 1. {
+                    glSamplerParameterf(
+                        sampler,
+                        GL_TEXTURE_MAX_ANISOTROPY_EXT,
+                        params.max_anisotropy.min(ctx.features.max_anisotropy),
+                    );
+                }
+                if let Some(compare) = params.compare {
+                    glSamplerParameteri(
+                        sampler,
+                        GL_TEXTURE_COMPARE_MODE,
+                        GL_COMPARE_REF_TO_TEXTURE as i32,
+                    );
+                    glSamplerParameteri(
+                        sampler,
+                        GL_TEXTURE_COMPARE_FUNC,
+                        GLenum::from(compare) as i32,
+                    );
+                }
+            }
+        }
+
+        Sampler { sampler, params }
+    }
+
+    /// Delete GPU sampler, leaving handle unmodified.
+    pub fn delete(&self) {
+        if self.sampler != 0 {
+            unsafe {
+                glDeleteSamplers(1, &self.sampler as *const _);
+            }
+        }
+    }
+}
+
+/// The part of the sampler state a texture holds on contexts without sampler objects.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) struct TextureSampling {
+    min_filter: GLint,
+    mag_filter: GLint,
+    wrap_s: GLint,
+    wrap_t: GLint,
+    max_anisotropy: f32,
+}
+
+impl TextureSampling {
+    pub(crate) fn from_params(params: &SamplerParams, max_anisotropy: f32) -> TextureSampling {
+        TextureSampling {
+            min_filter: min_filter(params.min_filter, params.mipmap_filter) as _,
+            mag_filter: params.mag_filter as _,
+            wrap_s: params.wrap_x as _,
+            wrap_t: params.wrap_y as _,
+            max_anisotropy: params.max_anisotropy.min(max_anisotropy),
+        }
+    }
+
+    /// State of the texture bound to `target`.
+    pub(crate) unsafe fn read(target: GLenum, max_anisotropy: f32) -> TextureSampling {
+        let mut sampling = TextureSampling {
+            min_filter: 0,
+            mag_filter: 0,
+            wrap_s: 0,
+            wrap_t: 0,
+            max_anisotropy: 1.,
+        };
+        glGetTexParameteriv(target, GL_TEXTURE_MIN_FILTER, &mut sampling.min_filter);
+        glGetTexParameteriv(target, GL_TEXTURE_MAG_FILTER, &mut sampling.mag_filter);
+        glGetTexParameteriv(target, GL_TEXTURE_WRAP_S, &mut sampling.wrap_s);
+        glGetTexParameteriv(target, GL_TEXTURE_WRAP_T, &mut sampling.wrap_t);
+        if max_anisotropy > 1. {
+            glGetTexParameterfv(
+                target,
+                GL_TEXTURE_MAX_ANISOTROPY_EXT,
+                &mut sampling.max_anisotropy,
+            );
+        }
+        sampling
+    }
+
+    /// Write the state into the texture bound to `target`.
+    pub(crate) unsafe fn write(&self, target: GLenum, max_anisotropy: f32) {
+        glTexParameteri(target, GL_TEXTURE_MIN_FILTER, self.min_filter);
+        glTexParameteri(target, GL_TEXTURE_MAG_FILTER, self.mag_filter);
+        glTexParameteri(target, GL_TEXTURE_WRAP_S, self.wrap_s);
+        glTexParameteri(target, GL_TEXTURE_WRAP_T, self.wrap_t);
+        if max_anisotropy > 1. {
+            glTexParameterf(target, GL_TEXTURE_MAX_ANISOTROPY_EXT, self.max_anisotropy);
+        }
+    }
+}
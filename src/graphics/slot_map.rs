@@ -0,0 +1,169 @@
+//! Storage for the resources owned by `GraphicsContext`.
+
+use std::ops::{Index, IndexMut};
+
+/// Index of a slot plus the generation the slot had when the resource was created.
+/// A deleted resource bumps the generation, so its stale handles never match a new
+/// resource reusing the slot.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub(crate) struct SlotId {
+    index: u32,
+    generation: u32,
+}
+
+struct Slot<T> {
+    generation: u32,
+    value: Option<T>,
+}
+
+/// Vec of slots with a free list. Indexing with a deleted id panics.
+pub(crate) struct SlotMap<T> {
+    /// Resource type name for the use-after-delete panic message.
+    name: &'static str,
+    slots: Vec<Slot<T>>,
+    free: Vec<u32>,
+}
+
+impl<T> SlotMap<T> {
+    pub(crate) fn new(name: &'static str) -> SlotMap<T> {
+        SlotMap {
+            name,
+            slots: vec![],
+            free: vec![],
+        }
+    }
+
+    pub(crate) fn insert(&mut self, value: T) -> SlotId {
+        match self.free.pop() {
+            Some(index) => {
+                let slot = &mut self.slots[index as usize];
+                slot.value = Some(value);
+                SlotId {
+                    index,
+                    generation: slot.generation,
+                }
+            }
+            None => {
+                self.slots.push(Slot {
+                    generation: 0,
+                    value: Some(value),
+                });
+                SlotId {
+                    index: self.slots.len() as u32 - 1,
+                    generation: 0,
+                }
+            }
+        }
+    }
+
+    /// Frees the slot. None if `id` is already deleted.
+    pub(crate) fn remove(&mut self, id: SlotId) -> Option<T> {
+        let slot = self.slots.get_mut(id.index as usize)?;
+        if slot.generation != id.generation {
+            return None;
+        }
+        let value = slot.value.take()?;
+        slot.generation = slot.generation.wrapping_add(1);
+        self.free.push(id.index);
+        Some(value)
+    }
+
+    pub(crate) fn get(&self, id: SlotId) -> Option<&T> {
+        self.slots
+            .get(id.index as usize)
+            .filter(|slot| slot.generation == id.generation)
+            .and_then(|slot| slot.value.as_ref())
+    }
+
+    pub(crate) fn get_mut(&mut self, id: SlotId) -> Option<&mut T> {
+        self.slots
+            .get_mut(id.index as usize)
+            .filter(|slot| slot.generation == id.generation)
+            .and_then(|slot| slot.value.as_mut())
+    }
+
+    pub(crate) fn iter(&self) -> impl Iterator<Item = &T> {
+        self.slots.iter().filter_map(|slot| slot.value.as_ref())
+    }
+
+    pub(crate) fn iter_mut(&mut self) -> impl Iterator<Item = &mut T> {
+        self.slots.iter_mut().filter_map(|slot| slot.value.as_mut())
+    }
+}
+
+impl<T> Index<SlotId> for SlotMap<T> {
+    type Output = T;
+
+    fn index(&self, id: SlotId) -> &T {
+        let name = self.name;
+        self.get(id)
+            .unwrap_or_else(|| panic!("{} used after delete", name))
+    }
+}
+
+impl<T> IndexMut<SlotId> for SlotMap<T> {
+    fn index_mut(&mut self, id: SlotId) -> &mut T {
+        let name = self.name;
+        self.get_mut(id)
+            .unwrap_or_else(|| panic!("{} used after delete", name))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn insert_get_remove() {
+        let mut map = SlotMap::new("Value");
+        let a = map.insert(1);
+        let b = map.insert(2);
+        assert_eq!(map[a], 1);
+        assert_eq!(map[b], 2);
+
+        assert_eq!(map.remove(a), Some(1));
+        assert_eq!(map.get(a), None);
+        assert_eq!(map.remove(a), None);
+        assert_eq!(map[b], 2);
+    }
+
+    #[test]
+    fn reused_slot_rejects_stale_id() {
+        let mut map = SlotMap::new("Value");
+        let old = map.insert(1);
+        map.remove(old);
+        let new = map.insert(2);
+
+        // same slot, next generation
+        assert_eq!(new.index, old.index);
+        assert_ne!(new, old);
+        assert_eq!(map.get(old), None);
+        assert_eq!(map.get_mut(old), None);
+        assert_eq!(map.remove(old), None);
+        assert_eq!(map[new], 2);
+    }
+
+    #[test]
+    fn iter_mut_skips_removed() {
+        let mut map = SlotMap::new("Value");
+        let a = map.insert(1);
+        map.insert(2);
+        map.insert(3);
+        map.remove(a);
+        for value in map.iter_mut() {
+            *value *= 10;
+        }
+        let mut values: Vec<_> = map.iter_mut().map(|value| *value).collect();
+        values.sort();
+        assert_eq!(values, vec![20, 30]);
+    }
+
+    #[test]
+    #[should_panic(expected = "Value used after delete")]
+    fn index_after_remove_panics() {
+        let mut map = SlotMap::new("Value");
+        let a = map.insert(1);
+        map.remove(a);
+        let _ = map[a];
+    }
+}
@@ -5,7 +5,12 @@ pub struct Texture {
     pub(crate) texture: GLuint,
     pub width: u32,
     pub height: u32,
+    /// Layers of `TextureKind::Array2D` or depth of `TextureKind::Texture3D`, 1 otherwise.
+    pub depth: u32,
     pub format: TextureFormat,
+    pub kind: TextureKind,
+    /// Number of mip levels, 1 for textures without mipmaps.
+    pub levels: u32,
 }
 
 impl Texture {
@@ -14,7 +19,10 @@ impl Texture {
             texture: 0,
             width: 0,
             height: 0,
+            depth: 1,
             format: TextureFormat::RGBA8,
+            kind: TextureKind::Texture2D,
+            levels: 1,
         }
     }
 
@@ -22,15 +30,31 @@ impl Texture {
         self.texture
     }
 
+    /// Wrap a 2D texture created outside of miniquad. Its size is unknown and left at 0x0,
+    /// so `update` and `read_pixels` are not usable with it.
+    ///
+    /// # Safety
+    ///
+    /// `texture` is a GL texture name of the current context, not deleted while the
+    /// `Texture` is in use.
     pub unsafe fn from_raw_id(texture: GLuint) -> Self {
         Self {
             texture,
             width: 0,
             height: 0,
+            depth: 1,
             format: TextureFormat::RGBA8, // assumed for now
+            kind: TextureKind::Texture2D,
+            levels: 1,
         }
     }
 
+    /// Name the texture in driver messages and frame debuggers.
+    /// Does nothing without `features.debug_output`.
+    pub fn label(&self, ctx: &mut Context, label: &str) {
+        ctx.object_label(GL_TEXTURE, self.texture, label);
+    }
+
     /// Delete GPU texture, leaving handle unmodified.
     ///
     /// More high-level code on top of miniquad probably is going to call this in Drop implementation of some
@@ -46,7 +70,9 @@ impl Texture {
 }
 
 /// List of all the possible formats of input data when uploading to texture.
-/// The list is built by intersection of texture formats supported by 3.3 core profile and webgl1.
+/// `RGB8`, `RGBA8`, `Depth` and `Alpha` are supported by both 3.3 core profile and webgl1.
+/// The rest are not available everywhere, see `Features::unsupported_texture_formats`
+/// and `Features::unrenderable_texture_formats`.
 #[repr(u8)]
 #[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
 pub enum TextureFormat {
@@ -54,9 +80,40 @@ pub enum TextureFormat {
     RGBA8,
     Depth,
     Alpha,
+    /// Half float RGBA, data is 4 x f16 per pixel.
+    RGBA16F,
+    /// Float RGBA, data is 4 x f32 per pixel.
+    RGBA32F,
+    /// Single f32 channel.
+    R32F,
+    /// Two u8 channels.
+    RG8,
+    /// Single f16 channel.
+    R16F,
+    /// RGBA8 in sRGB color space, converted to linear on sampling.
+    #[allow(non_camel_case_types)]
+    SRGB8_ALPHA8,
+    /// Packed 24 bit depth and 8 bit stencil, usable as a `RenderPass` depth attachment
+    /// with stencil.
+    Depth24Stencil8,
+    /// S3TC DXT1 with 1 bit alpha, 4x4 blocks of 8 bytes.
+    BC1,
+    /// S3TC DXT3, 4x4 blocks of 16 bytes.
+    BC2,
+    /// S3TC DXT5, 4x4 blocks of 16 bytes.
+    BC3,
+    /// 4x4 blocks of 8 bytes.
+    ETC2RGB8,
+    /// ETC2 with EAC alpha, 4x4 blocks of 16 bytes.
+    ETC2RGBA8,
+    /// 4x4 blocks of 16 bytes.
+    ASTC4x4,
+    /// 8x8 blocks of 16 bytes.
+    ASTC8x8,
 }
 
 /// Converts from TextureFormat to (internal_format, format, pixel_type)
+/// For compressed formats only internal_format is meaningful.
 impl From<TextureFormat> for (GLenum, GLenum, GLenum) {
     fn from(format: TextureFormat) -> Self {
         match format {
@@ -67,18 +124,86 @@ impl From<TextureFormat> for (GLenum, GLenum, GLenum) {
             TextureFormat::Alpha => (GL_ALPHA, GL_ALPHA, GL_UNSIGNED_BYTE),
             #[cfg(not(target_arch = "wasm32"))]
             TextureFormat::Alpha => (GL_R8, GL_RED, GL_UNSIGNED_BYTE), // texture updates will swizzle Red -> Alpha to match WASM
+            TextureFormat::RGBA16F => (GL_RGBA16F, GL_RGBA, GL_HALF_FLOAT),
+            TextureFormat::RGBA32F => (GL_RGBA32F, GL_RGBA, GL_FLOAT),
+            TextureFormat::R32F => (GL_R32F, GL_RED, GL_FLOAT),
+            TextureFormat::RG8 => (GL_RG8, GL_RG, GL_UNSIGNED_BYTE),
+            TextureFormat::R16F => (GL_R16F, GL_RED, GL_HALF_FLOAT),
+            TextureFormat::SRGB8_ALPHA8 => (GL_SRGB8_ALPHA8, GL_RGBA, GL_UNSIGNED_BYTE),
+            TextureFormat::Depth24Stencil8 => {
+                (GL_DEPTH24_STENCIL8, GL_DEPTH_STENCIL, GL_UNSIGNED_INT_24_8)
+            }
+            TextureFormat::BC1 => (GL_COMPRESSED_RGBA_S3TC_DXT1_EXT, GL_RGBA, GL_UNSIGNED_BYTE),
+            TextureFormat::BC2 => (GL_COMPRESSED_RGBA_S3TC_DXT3_EXT, GL_RGBA, GL_UNSIGNED_BYTE),
+            TextureFormat::BC3 => (GL_COMPRESSED_RGBA_S3TC_DXT5_EXT, GL_RGBA, GL_UNSIGNED_BYTE),
+            TextureFormat::ETC2RGB8 => (GL_COMPRESSED_RGB8_ETC2, GL_RGB, GL_UNSIGNED_BYTE),
+            TextureFormat::ETC2RGBA8 => (GL_COMPRESSED_RGBA8_ETC2_EAC, GL_RGBA, GL_UNSIGNED_BYTE),
+            TextureFormat::ASTC4x4 => (GL_COMPRESSED_RGBA_ASTC_4x4_KHR, GL_RGBA, GL_UNSIGNED_BYTE),
+            TextureFormat::ASTC8x8 => (GL_COMPRESSED_RGBA_ASTC_8x8_KHR, GL_RGBA, GL_UNSIGNED_BYTE),
         }
     }
 }
 impl TextureFormat {
     /// Returns the size in bytes of texture with `dimensions`.
     pub fn size(self, width: u32, height: u32) -> u32 {
+        if let Some((block_width, block_height, block_size)) = self.block() {
+            let blocks_x = width.div_ceil(block_width);
+            let blocks_y = height.div_ceil(block_height);
+            return blocks_x * blocks_y * block_size;
+        }
+
         let square = width * height;
         match self {
             TextureFormat::RGB8 => 3 * square,
             TextureFormat::RGBA8 => 4 * square,
             TextureFormat::Depth => 2 * square,
             TextureFormat::Alpha => 1 * square,
+            TextureFormat::RGBA16F => 8 * square,
+            TextureFormat::RGBA32F => 16 * square,
+            TextureFormat::R32F => 4 * square,
+            TextureFormat::RG8 => 2 * square,
+            TextureFormat::R16F => 2 * square,
+            TextureFormat::SRGB8_ALPHA8 => 4 * square,
+            TextureFormat::Depth24Stencil8 => 4 * square,
+            _ => unreachable!(),
+        }
+    }
+
+    /// (width, height, size in bytes) of a block of the compressed format.
+    pub fn block(self) -> Option<(u32, u32, u32)> {
+        match self {
+            TextureFormat::BC1 | TextureFormat::ETC2RGB8 => Some((4, 4, 8)),
+            TextureFormat::BC2
+            | TextureFormat::BC3
+            | TextureFormat::ETC2RGBA8
+            | TextureFormat::ASTC4x4 => Some((4, 4, 16)),
+            TextureFormat::ASTC8x8 => Some((8, 8, 16)),
+            _ => None,
+        }
+    }
+
+    pub fn is_compressed(self) -> bool {
+        self.block().is_some()
+    }
+
+    /// Framebuffer attachment point for depth formats, None for color formats.
+    pub(crate) fn depth_attachment(self) -> Option<GLenum> {
+        match self {
+            TextureFormat::Depth => Some(GL_DEPTH_ATTACHMENT),
+            TextureFormat::Depth24Stencil8 => Some(GL_DEPTH_STENCIL_ATTACHMENT),
+            _ => None,
+        }
+    }
+
+    /// Sized internal format, as required by `glRenderbufferStorageMultisample`.
+    pub(crate) fn renderbuffer_format(self) -> GLenum {
+        match self {
+            TextureFormat::RGB8 => GL_RGB8,
+            TextureFormat::RGBA8 => GL_RGBA8,
+            TextureFormat::Depth => GL_DEPTH_COMPONENT16,
+            TextureFormat::Alpha => GL_R8,
+            // the rest are sized already
+            _ => <(GLenum, GLenum, GLenum)>::from(self).0,
         }
     }
 }
@@ -91,10 +216,40 @@ impl Default for TextureParams {
             filter: FilterMode::Linear,
             width: 0,
             height: 0,
+            depth: 1,
+            kind: TextureKind::Texture2D,
+            mipmaps: false,
+            mipmap_filter: MipmapFilterMode::None,
         }
     }
 }
 
+/// Texture type, defines the GL binding target.
+///
+/// NOTE: `Array2D` and `Texture3D` are not supported on gles2 and webgl1.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+pub enum TextureKind {
+    Texture2D = GL_TEXTURE_2D as isize,
+    /// Six square faces, laid out in `CubeFace` order.
+    CubeMap = GL_TEXTURE_CUBE_MAP as isize,
+    /// `depth` layers of 2D images.
+    Array2D = GL_TEXTURE_2D_ARRAY as isize,
+    /// Volume texture `depth` slices deep.
+    Texture3D = GL_TEXTURE_3D as isize,
+}
+
+/// Faces of a `TextureKind::CubeMap`, in the order faces are stored in the texture data.
+/// `face as u32` is the layer index for `Texture::update_layer` and `RenderPass::new_layer`.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+pub enum CubeFace {
+    PositiveX = 0,
+    NegativeX = 1,
+    PositiveY = 2,
+    NegativeY = 3,
+    PositiveZ = 4,
+    NegativeZ = 5,
+}
+
 /// Sets the wrap parameter for texture.
 #[derive(Debug, PartialEq, Eq, Clone, Copy)]
 pub enum TextureWrap {
@@ -104,6 +259,10 @@ pub enum TextureWrap {
     Mirror = GL_MIRRORED_REPEAT as isize,
     /// Samples at coord x + 1 map to coord 1.
     Clamp = GL_CLAMP_TO_EDGE as isize,
+    /// Samples outside of the texture get `SamplerParams::border_color`.
+    ///
+    /// NOTE: not supported on gles3.0, gles2 and webgl.
+    ClampToBorder = GL_CLAMP_TO_BORDER as isize,
 }
 
 #[derive(Clone, Copy, Debug, PartialEq, Hash)]
@@ -112,6 +271,47 @@ pub enum FilterMode {
     Nearest = GL_NEAREST as isize,
 }
 
+/// Filtering between mip levels.
+/// Together with `FilterMode` makes the texture's min filter.
+#[derive(Clone, Copy, Debug, PartialEq, Hash)]
+pub enum MipmapFilterMode {
+    /// Only level 0 is sampled.
+    None,
+    Linear,
+    Nearest,
+}
+
+pub(crate) fn min_filter(filter: FilterMode, mipmap_filter: MipmapFilterMode) -> GLenum {
+    match (filter, mipmap_filter) {
+        (_, MipmapFilterMode::None) => filter as GLenum,
+        (FilterMode::Linear, MipmapFilterMode::Linear) => GL_LINEAR_MIPMAP_LINEAR,
+        (FilterMode::Linear, MipmapFilterMode::Nearest) => GL_LINEAR_MIPMAP_NEAREST,
+        (FilterMode::Nearest, MipmapFilterMode::Linear) => GL_NEAREST_MIPMAP_LINEAR,
+        (FilterMode::Nearest, MipmapFilterMode::Nearest) => GL_NEAREST_MIPMAP_NEAREST,
+    }
+}
+
+/// Part of a texture image for `Texture::update_layer_part` and `update_level_part`,
+/// in the pixels of the updated level.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+pub struct TextureRect {
+    pub x: i32,
+    pub y: i32,
+    pub width: i32,
+    pub height: i32,
+}
+
+impl TextureRect {
+    pub fn new(x: i32, y: i32, width: i32, height: i32) -> TextureRect {
+        TextureRect {
+            x,
+            y,
+            width,
+            height,
+        }
+    }
+}
+
 #[derive(Debug, PartialEq, Eq, Clone, Copy)]
 pub enum TextureAccess {
     /// Used as read-only from GPU
@@ -127,6 +327,18 @@ pub struct TextureParams {
     pub filter: FilterMode,
     pub width: u32,
     pub height: u32,
+    /// Layers of `TextureKind::Array2D` or depth of `TextureKind::Texture3D`,
+    /// ignored for other kinds.
+    pub depth: u32,
+    pub kind: TextureKind,
+    /// Allocate the full mipmap chain.
+    /// Initial data goes to level 0 and the rest of the levels are generated from it.
+    /// Render pass textures get their mipmaps regenerated on `end_render_pass`.
+    ///
+    /// NOTE: on webgl1 only power of two textures can have mipmaps.
+    pub mipmaps: bool,
+    /// Mipmap part of the min filter, makes sense only with `mipmaps`.
+    pub mipmap_filter: MipmapFilterMode,
 }
 
 impl Texture {
@@ -141,64 +353,74 @@ impl Texture {
         bytes: Option<&[u8]>,
         params: TextureParams,
     ) -> Texture {
+        let mut texture = Texture {
+            texture: 0,
+            width: params.width,
+            height: params.height,
+            depth: match params.kind {
+                TextureKind::Array2D | TextureKind::Texture3D => params.depth,
+                _ => 1,
+            },
+            format: params.format,
+            kind: params.kind,
+            levels: 1,
+        };
+        if params.mipmaps {
+            texture.levels = texture.full_mip_chain_levels();
+        }
+
         if let Some(bytes_data) = bytes {
             assert_eq!(
-                params.format.size(params.width, params.height) as usize,
+                texture.size(params.width, params.height) * texture.layers() as usize,
                 bytes_data.len()
             );
         }
 
-        let (internal_format, format, pixel_type) = params.format.into();
+        let target = texture.target();
 
         ctx.cache.store_texture_binding(0);
 
-        let mut texture: GLuint = 0;
-
         unsafe {
-            glGenTextures(1, &mut texture as *mut _);
-            ctx.cache.bind_texture(0, texture);
-            glPixelStorei(GL_UNPACK_ALIGNMENT, 1); // miniquad always uses row alignment of 1
+            glGenTextures(1, &mut texture.texture as *mut _);
+            ctx.cache.bind_texture(0, target, texture.texture);
+            // GL names are reused, the entry may be left from a deleted texture
+            ctx.emulated_samplers.remove(&texture.texture);
 
-            glTexImage2D(
-                GL_TEXTURE_2D,
-                0,
-                internal_format as i32,
-                params.width as i32,
-                params.height as i32,
-                0,
-                format,
-                pixel_type,
-                match bytes {
-                    Some(bytes) => bytes.as_ptr() as *const _,
-                    Option::None => std::ptr::null(),
-                },
+            texture.tex_image(bytes);
+
+            glTexParameteri(target, GL_TEXTURE_WRAP_S, params.wrap as i32);
+            glTexParameteri(target, GL_TEXTURE_WRAP_T, params.wrap as i32);
+            if params.kind == TextureKind::Texture3D {
+                glTexParameteri(target, GL_TEXTURE_WRAP_R, params.wrap as i32);
+            }
+            glTexParameteri(
+                target,
+                GL_TEXTURE_MIN_FILTER,
+                min_filter(params.filter, params.mipmap_filter) as i32,
             );
+            glTexParameteri(target, GL_TEXTURE_MAG_FILTER, params.filter as i32);
 
-            glTexParameteri(GL_TEXTURE_2D, GL_TEXTURE_WRAP_S, params.wrap as i32);
-            glTexParameteri(GL_TEXTURE_2D, GL_TEXTURE_WRAP_T, params.wrap as i32);
-            glTexParameteri(GL_TEXTURE_2D, GL_TEXTURE_MIN_FILTER, params.filter as i32);
-            glTexParameteri(GL_TEXTURE_2D, GL_TEXTURE_MAG_FILTER, params.filter as i32);
+            // there is no mipmap generation for compressed textures,
+            // levels should be uploaded with `update_level`
+            if bytes.is_some() && texture.levels > 1 && !params.format.is_compressed() {
+                glGenerateMipmap(target);
+            }
 
             if cfg!(not(target_arch = "wasm32")) {
                 // if not WASM
                 if params.format == TextureFormat::Alpha {
                     // if alpha miniquad texture, the value on non-WASM is stored in red channel
                     // swizzle red -> alpha
-                    glTexParameteri(GL_TEXTURE_2D, GL_TEXTURE_SWIZZLE_A, GL_RED as _);
+                    glTexParameteri(target, GL_TEXTURE_SWIZZLE_A, GL_RED as _);
                 } else {
                     // keep alpha -> alpha
-                    glTexParameteri(GL_TEXTURE_2D, GL_TEXTURE_SWIZZLE_A, GL_ALPHA as _);
+                    glTexParameteri(target, GL_TEXTURE_SWIZZLE_A, GL_ALPHA as _);
                 }
             }
         }
         ctx.cache.restore_texture_binding(0);
 
-        Texture {
-            texture,
-            width: params.width,
-            height: params.height,
-            format: params.format,
-        }
+        texture
     }
 
     /// Upload texture to GPU with given TextureParams
@@ -219,6 +441,7 @@ impl Texture {
                 format: TextureFormat::RGBA8,
                 wrap: TextureWrap::Clamp,
                 filter: FilterMode::Linear,
+                ..Default::default()
             },
         )
     }
@@ -226,10 +449,11 @@ impl Texture {
     /// Set the min and mag filter to `filter`
     pub fn set_filter(&self, ctx: &mut Context, filter: FilterMode) {
         ctx.cache.store_texture_binding(0);
-        ctx.cache.bind_texture(0, self.texture);
+        ctx.cache.bind_texture(0, self.target(), self.texture);
+        unsafe { ctx.restore_texture_sampling(self.target(), self.texture) };
         unsafe {
-            glTexParameteri(GL_TEXTURE_2D, GL_TEXTURE_MIN_FILTER, filter as i32);
-            glTexParameteri(GL_TEXTURE_2D, GL_TEXTURE_MAG_FILTER, filter as i32);
+            glTexParameteri(self.target(), GL_TEXTURE_MIN_FILTER, filter as i32);
+            glTexParameteri(self.target(), GL_TEXTURE_MAG_FILTER, filter as i32);
         }
         ctx.cache.restore_texture_binding(0);
     }
@@ -242,21 +466,57 @@ impl Texture {
         mag_filter: FilterMode,
     ) {
         ctx.cache.store_texture_binding(0);
-        ctx.cache.bind_texture(0, self.texture);
+        ctx.cache.bind_texture(0, self.target(), self.texture);
+        unsafe { ctx.restore_texture_sampling(self.target(), self.texture) };
+        unsafe {
+            glTexParameteri(self.target(), GL_TEXTURE_MIN_FILTER, min_filter as i32);
+            glTexParameteri(self.target(), GL_TEXTURE_MAG_FILTER, mag_filter as i32);
+        }
+        ctx.cache.restore_texture_binding(0);
+    }
+
+    /// Set the min filter of a texture with mipmaps
+    pub fn set_mipmap_filter(
+        &self,
+        ctx: &mut Context,
+        filter: FilterMode,
+        mipmap_filter: MipmapFilterMode,
+    ) {
+        ctx.cache.store_texture_binding(0);
+        ctx.cache.bind_texture(0, self.target(), self.texture);
+        unsafe { ctx.restore_texture_sampling(self.target(), self.texture) };
+        unsafe {
+            glTexParameteri(
+                self.target(),
+                GL_TEXTURE_MIN_FILTER,
+                min_filter(filter, mipmap_filter) as i32,
+            );
+        }
+        ctx.cache.restore_texture_binding(0);
+    }
+
+    /// Fill all mip levels from level 0.
+    /// Does nothing useful for textures created without `TextureParams::mipmaps`.
+    pub fn generate_mipmaps(&self, ctx: &mut Context) {
+        ctx.cache.store_texture_binding(0);
+        ctx.cache.bind_texture(0, self.target(), self.texture);
         unsafe {
-            glTexParameteri(GL_TEXTURE_2D, GL_TEXTURE_MIN_FILTER, min_filter as i32);
-            glTexParameteri(GL_TEXTURE_2D, GL_TEXTURE_MAG_FILTER, mag_filter as i32);
+            glGenerateMipmap(self.target());
         }
         ctx.cache.restore_texture_binding(0);
     }
 
-    /// Set x and y wrap to `wrap`
+    /// Set x and y wrap to `wrap`, and z wrap for 3D textures
     pub fn set_wrap(&self, ctx: &mut Context, wrap: TextureWrap) {
         ctx.cache.store_texture_binding(0);
-        ctx.cache.bind_texture(0, self.texture);
+        ctx.cache.bind_texture(0, self.target(), self.texture);
+        unsafe { ctx.restore_texture_sampling(self.target(), self.texture) };
         unsafe {
-            glTexParameteri(GL_TEXTURE_2D, GL_TEXTURE_WRAP_S, wrap as i32);
-            glTexParameteri(GL_TEXTURE_2D, GL_TEXTURE_WRAP_T, wrap as i32);
+            glTexParameteri(self.target(), GL_TEXTURE_WRAP_S, wrap as i32);
+            glTexParameteri(self.target(), GL_TEXTURE_WRAP_T, wrap as i32);
+            if self.kind == TextureKind::Texture3D {
+                glTexParameteri(self.target(), GL_TEXTURE_WRAP_R, wrap as i32);
+            }
         }
         ctx.cache.restore_texture_binding(0);
     }
@@ -264,45 +524,143 @@ impl Texture {
     /// Set x and y wrap separately
     pub fn set_wrap_xy(&self, ctx: &mut Context, x_wrap: TextureWrap, y_wrap: TextureWrap) {
         ctx.cache.store_texture_binding(0);
-        ctx.cache.bind_texture(0, self.texture);
+        ctx.cache.bind_texture(0, self.target(), self.texture);
+        unsafe { ctx.restore_texture_sampling(self.target(), self.texture) };
         unsafe {
-            glTexParameteri(GL_TEXTURE_2D, GL_TEXTURE_WRAP_S, x_wrap as i32);
-            glTexParameteri(GL_TEXTURE_2D, GL_TEXTURE_WRAP_T, y_wrap as i32);
+            glTexParameteri(self.target(), GL_TEXTURE_WRAP_S, x_wrap as i32);
+            glTexParameteri(self.target(), GL_TEXTURE_WRAP_T, y_wrap as i32);
         }
         ctx.cache.restore_texture_binding(0);
     }
 
+    /// Resize all the layers of the texture, `depth` is kept as is.
+    /// Textures with mipmaps get a new full mip chain, with level 0 set to `bytes`
+    /// and the rest generated from it, as in `Texture::new`. Compressed textures
+    /// can't generate mipmaps, their levels should be uploaded with `update_level`.
     pub fn resize(&mut self, ctx: &mut Context, width: u32, height: u32, bytes: Option<&[u8]>) {
         ctx.cache.store_texture_binding(0);
-        ctx.cache.bind_texture(0, self.texture);
-
-        let (internal_format, format, pixel_type) = self.format.into();
+        ctx.cache.bind_texture(0, self.target(), self.texture);
 
         self.width = width;
         self.height = height;
+        if self.levels > 1 {
+            self.levels = self.full_mip_chain_levels();
+        }
 
         unsafe {
-            glPixelStorei(GL_UNPACK_ALIGNMENT, 1); // miniquad always uses row alignment of 1
+            self.tex_image(bytes);
 
-            glTexImage2D(
-                GL_TEXTURE_2D,
-                0,
-                internal_format as i32,
-                self.width as i32,
-                self.height as i32,
-                0,
-                format,
-                pixel_type,
-                match bytes {
-                    Some(bytes) => bytes.as_ptr() as *const _,
-                    Option::None => std::ptr::null(),
-                },
-            );
+            if bytes.is_some() && self.levels > 1 && !self.format.is_compressed() {
+                glGenerateMipmap(self.target());
+            }
         }
 
         ctx.cache.restore_texture_binding(0);
     }
 
+    /// (Re)allocate storage for every layer and mip level of the currently bound texture.
+    /// `bytes`, if any, holds the level 0 data of all the layers one after another.
+    unsafe fn tex_image(&self, bytes: Option<&[u8]>) {
+        if self.format.is_compressed() {
+            self.compressed_tex_image(bytes);
+            return;
+        }
+
+        let (internal_format, format, pixel_type) = self.format.into();
+        let layer_size = self.size(self.width, self.height);
+        let data = |level: u32, layer: usize| match bytes {
+            Some(bytes) if level == 0 => bytes[layer * layer_size..].as_ptr() as *const _,
+            _ => std::ptr::null(),
+        };
+
+        glPixelStorei(GL_UNPACK_ALIGNMENT, 1); // miniquad always uses row alignment of 1
+
+        for level in 0..self.levels {
+            let (width, height) = self.level_size(level);
+            match self.kind {
+                TextureKind::Texture2D | TextureKind::CubeMap => {
+                    for layer in 0..self.layers() {
+                        glTexImage2D(
+                            self.layer_target(layer),
+                            level as _,
+                            internal_format as i32,
+                            width as i32,
+                            height as i32,
+                            0,
+                            format,
+                            pixel_type,
+                            data(level, layer as usize),
+                        );
+                    }
+                }
+                TextureKind::Array2D | TextureKind::Texture3D => {
+                    glTexImage3D(
+                        self.target(),
+                        level as _,
+                        internal_format as i32,
+                        width as i32,
+                        height as i32,
+                        self.level_depth(level) as i32,
+                        0,
+                        format,
+                        pixel_type,
+                        data(level, 0),
+                    );
+                }
+            }
+        }
+    }
+
+    /// `tex_image` for compressed formats.
+    /// Compressed storage can't be allocated without data, missing levels are zero-filled.
+    unsafe fn compressed_tex_image(&self, bytes: Option<&[u8]>) {
+        let (internal_format, _, _) = self.format.into();
+        let layer_size = self.size(self.width, self.height);
+
+        for level in 0..self.levels {
+            let (width, height) = self.level_size(level);
+            let level_layer_size = self.size(width, height);
+            let zeroed = vec![0u8; level_layer_size * self.level_depth(level) as usize];
+            let data = |layer: usize, size: usize| match bytes {
+                Some(bytes) if level == 0 => &bytes[layer * layer_size..layer * layer_size + size],
+                _ => &zeroed[..size],
+            };
+
+            match self.kind {
+                TextureKind::Texture2D | TextureKind::CubeMap => {
+                    for layer in 0..self.layers() {
+                        let data = data(layer as usize, level_layer_size);
+                        glCompressedTexImage2D(
+                            self.layer_target(layer),
+                            level as _,
+                            internal_format,
+                            width as _,
+                            height as _,
+                            0,
+                            data.len() as _,
+                            data.as_ptr() as *const _,
+                        );
+                    }
+                }
+                TextureKind::Array2D | TextureKind::Texture3D => {
+                    let depth = self.level_depth(level);
+                    let data = data(0, level_layer_size * depth as usize);
+                    glCompressedTexImage3D(
+                        self.target(),
+                        level as _,
+                        internal_format,
+                        width as _,
+                        height as _,
+                        depth as _,
+                        0,
+                        data.len() as _,
+                        data.as_ptr() as *const _,
+                    );
+                }
+            }
+        }
+    }
+
     /// Update whole texture content
     /// bytes should be width * height * 4 size - non rgba8 textures are not supported yet anyway
     pub fn update(&self, ctx: &mut Context, bytes: &[u8]) {
@@ -318,6 +676,23 @@ impl Texture {
         )
     }
 
+    /// Update whole content of one layer: a cube map face, a 2D array layer or a 3D slice.
+    pub fn update_layer(&self, ctx: &mut Context, layer: u32, bytes: &[u8]) {
+        assert_eq!(self.size(self.width, self.height), bytes.len());
+
+        let rect = TextureRect::new(0, 0, self.width as _, self.height as _);
+        self.update_layer_part(ctx, layer, rect, bytes)
+    }
+
+    /// Update whole content of one mip level of the first layer.
+    pub fn update_level(&self, ctx: &mut Context, level: u32, bytes: &[u8]) {
+        let (width, height) = self.level_size(level);
+
+        let rect = TextureRect::new(0, 0, width as _, height as _);
+        self.update_level_part(ctx, 0, level, rect, bytes)
+    }
+
+    /// Update a part of the first layer, see `update_layer_part`.
     pub fn update_texture_part(
         &self,
         ctx: &mut Context,
@@ -327,40 +702,147 @@ impl Texture {
         height: i32,
         bytes: &[u8],
     ) {
+        let rect = TextureRect::new(x_offset, y_offset, width, height);
+        self.update_layer_part(ctx, 0, rect, bytes)
+    }
+
+    /// Update a part of level 0 of one layer, see `update_level_part`.
+    pub fn update_layer_part(
+        &self,
+        ctx: &mut Context,
+        layer: u32,
+        rect: TextureRect,
+        bytes: &[u8],
+    ) {
+        self.update_level_part(ctx, layer, 0, rect, bytes)
+    }
+
+    /// Update a part of one mip level of one layer.
+    /// 3D textures have `max(1, depth >> level)` slices at the level.
+    pub fn update_level_part(
+        &self,
+        ctx: &mut Context,
+        layer: u32,
+        level: u32,
+        rect: TextureRect,
+        bytes: &[u8],
+    ) {
+        let TextureRect {
+            x: x_offset,
+            y: y_offset,
+            width,
+            height,
+        } = rect;
+        let (level_width, level_height) = self.level_size(level);
+        let level_layers = match self.kind {
+            TextureKind::Array2D | TextureKind::Texture3D => self.level_depth(level),
+            _ => self.layers(),
+        };
         assert_eq!(self.size(width as _, height as _), bytes.len());
-        assert!(x_offset + width <= self.width as _);
-        assert!(y_offset + height <= self.height as _);
+        assert!(x_offset + width <= level_width as _);
+        assert!(y_offset + height <= level_height as _);
+        assert!(layer < level_layers);
+        assert!(level < self.levels);
 
         ctx.cache.store_texture_binding(0);
-        ctx.cache.bind_texture(0, self.texture);
+        ctx.cache.bind_texture(0, self.target(), self.texture);
 
-        let (_, format, pixel_type) = self.format.into();
+        let (internal_format, format, pixel_type) = self.format.into();
 
         unsafe {
             glPixelStorei(GL_UNPACK_ALIGNMENT, 1); // miniquad always uses row alignment of 1
 
-            glTexSubImage2D(
-                GL_TEXTURE_2D,
-                0,
-                x_offset as _,
-                y_offset as _,
-                width as _,
-                height as _,
-                format,
-                pixel_type,
-                bytes.as_ptr() as *const _,
-            );
+            match self.kind {
+                // offsets and sizes of compressed updates should be block aligned
+                TextureKind::Texture2D | TextureKind::CubeMap if self.format.is_compressed() => {
+                    glCompressedTexSubImage2D(
+                        self.layer_target(layer),
+                        level as _,
+                        x_offset as _,
+                        y_offset as _,
+                        width as _,
+                        height as _,
+                        internal_format,
+                        bytes.len() as _,
+                        bytes.as_ptr() as *const _,
+                    );
+                }
+                TextureKind::Array2D | TextureKind::Texture3D if self.format.is_compressed() => {
+                    glCompressedTexSubImage3D(
+                        self.target(),
+                        level as _,
+                        x_offset as _,
+                        y_offset as _,
+                        layer as _,
+                        width as _,
+                        height as _,
+                        1,
+                        internal_format,
+                        bytes.len() as _,
+                        bytes.as_ptr() as *const _,
+                    );
+                }
+                TextureKind::Texture2D | TextureKind::CubeMap => {
+                    glTexSubImage2D(
+                        self.layer_target(layer),
+                        level as _,
+                        x_offset as _,
+                        y_offset as _,
+                        width as _,
+                        height as _,
+                        format,
+                        pixel_type,
+                        bytes.as_ptr() as *const _,
+                    );
+                }
+                TextureKind::Array2D | TextureKind::Texture3D => {
+                    glTexSubImage3D(
+                        self.target(),
+                        level as _,
+                        x_offset as _,
+                        y_offset as _,
+                        layer as _,
+                        width as _,
+                        height as _,
+                        1,
+                        format,
+                        pixel_type,
+                        bytes.as_ptr() as *const _,
+                    );
+                }
+            }
         }
 
         ctx.cache.restore_texture_binding(0);
     }
 
-    /// Read texture data into CPU memory
+    /// Read texture data into CPU memory.
+    /// Layers of cube maps, arrays and 3D textures are read one after another,
+    /// `bytes` has to hold `layers()` times the size of the level 0 image.
+    ///
+    /// Pixels are read with the format and type they are uploaded with. Desktop gl
+    /// accepts that for every uncompressed format except `Alpha`, while gles3 and webgl
+    /// only guarantee `RGBA8` and `SRGB8_ALPHA8`, and the float formats with
+    /// `EXT_color_buffer_float`. Other formats, depth ones in particular, may fail there
+    /// with GL_INVALID_OPERATION, leaving `bytes` untouched.
+    ///
+    /// Panics for compressed formats, GL has no way to read them back.
     pub fn read_pixels(&self, bytes: &mut [u8]) {
         if self.format == TextureFormat::Alpha {
             unimplemented!("read_pixels is not implement for Alpha textures");
         }
+        assert!(
+            !self.format.is_compressed(),
+            "read_pixels is not supported for compressed textures"
+        );
         let (_, format, pixel_type) = self.format.into();
+        let layer_size = self.size(self.width, self.height);
+        assert!(
+            bytes.len() >= self.layers() as usize * layer_size,
+            "read_pixels needs {} bytes, got {}",
+            self.layers() as usize * layer_size,
+            bytes.len()
+        );
 
         let mut fbo = 0;
         unsafe {
@@ -368,29 +850,99 @@ impl Texture {
             glGetIntegerv(gl::GL_DRAW_FRAMEBUFFER_BINDING, &mut binded_fbo);
             glGenFramebuffers(1, &mut fbo);
             glBindFramebuffer(gl::GL_FRAMEBUFFER, fbo);
-            glFramebufferTexture2D(
-                gl::GL_FRAMEBUFFER,
-                gl::GL_COLOR_ATTACHMENT0,
-                gl::GL_TEXTURE_2D,
-                self.texture,
-                0,
-            );
 
-            glReadPixels(
-                0,
-                0,
-                self.width as _,
-                self.height as _,
-                format,
-                pixel_type,
-                bytes.as_mut_ptr() as _,
-            );
+            let attachment = self
+                .format
+                .depth_attachment()
+                .unwrap_or(gl::GL_COLOR_ATTACHMENT0);
+            glPixelStorei(GL_PACK_ALIGNMENT, 1); // rows are tightly packed, as in `size`
+            for (layer, layer_bytes) in bytes
+                .chunks_exact_mut(layer_size)
+                .take(self.layers() as usize)
+                .enumerate()
+            {
+                self.attach_to_framebuffer(attachment, layer as u32);
+
+                glReadPixels(
+                    0,
+                    0,
+                    self.width as _,
+                    self.height as _,
+                    format,
+                    pixel_type,
+                    layer_bytes.as_mut_ptr() as _,
+                );
+            }
 
             glBindFramebuffer(gl::GL_FRAMEBUFFER, binded_fbo as _);
             glDeleteFramebuffers(1, &fbo);
         }
     }
 
+    /// Attach one layer of the texture to the currently bound framebuffer.
+    pub(crate) unsafe fn attach_to_framebuffer(&self, attachment: GLenum, layer: u32) {
+        match self.kind {
+            TextureKind::Texture2D | TextureKind::CubeMap => {
+                glFramebufferTexture2D(
+                    GL_FRAMEBUFFER,
+                    attachment,
+                    self.layer_target(layer),
+                    self.texture,
+                    0,
+                );
+            }
+            TextureKind::Array2D | TextureKind::Texture3D => {
+                glFramebufferTextureLayer(GL_FRAMEBUFFER, attachment, self.texture, 0, layer as _);
+            }
+        }
+    }
+
+    /// Number of 2D images in the texture: 6 faces of a cube map,
+    /// `depth` for arrays and 3D textures.
+    pub fn layers(&self) -> u32 {
+        match self.kind {
+            TextureKind::Texture2D => 1,
+            TextureKind::CubeMap => 6,
+            TextureKind::Array2D | TextureKind::Texture3D => self.depth,
+        }
+    }
+
+    /// Size of the given mip level.
+    pub fn level_size(&self, level: u32) -> (u32, u32) {
+        ((self.width >> level).max(1), (self.height >> level).max(1))
+    }
+
+    /// Layers of arrays, or slices of a 3D texture at the given mip level.
+    fn level_depth(&self, level: u32) -> u32 {
+        match self.kind {
+            TextureKind::Texture3D => (self.depth >> level).max(1),
+            TextureKind::Array2D => self.depth,
+            _ => 1,
+        }
+    }
+
+    /// Levels count down to 1x1 (and 1 slice deep, for 3D textures).
+    fn full_mip_chain_levels(&self) -> u32 {
+        let mut size = self.width.max(self.height);
+        if self.kind == TextureKind::Texture3D {
+            size = size.max(self.depth);
+        }
+        32 - size.max(1).leading_zeros()
+    }
+
+    #[inline]
+    fn target(&self) -> GLenum {
+        self.kind as GLenum
+    }
+
+    /// Target for glTexImage2D-like calls, different for each face of a cube map.
+    fn layer_target(&self, layer: u32) -> GLenum {
+        match self.kind {
+            TextureKind::CubeMap => GL_TEXTURE_CUBE_MAP_POSITIVE_X + layer,
+            _ => self.target(),
+        }
+    }
+
     #[inline]
     fn size(&self, width: u32, height: u32) -> usize {
         self.format.size(width, height) as usize
@@ -0,0 +1,109 @@
+//! Polling watcher reloading shaders when their source files change on disk.
+
+use std::{
+    path::{Path, PathBuf},
+    time::SystemTime,
+};
+
+use crate::Context;
+
+use super::{Preprocessor, Shader, ShaderError};
+
+struct WatchedShader {
+    shader: Shader,
+    vertex_path: PathBuf,
+    fragment_path: PathBuf,
+    modified: Option<SystemTime>,
+}
+
+/// Calls `Shader::reload` for shaders with changed source files.
+/// Only the watched files themselves are checked, not their includes.
+///
+/// ```ignore
+/// // on init
+/// watcher.watch(shader, "shaders/sprite.vert", "shaders/sprite.frag");
+/// // every frame
+/// for (_, res) in watcher.poll(ctx) {
+///     if let Err(err) = res {
+///         eprintln!("{}", err);
+///     }
+/// }
+/// ```
+#[derive(Default)]
+pub struct ShaderWatcher {
+    preprocessor: Option<Preprocessor>,
+    shaders: Vec<WatchedShader>,
+}
+
+impl ShaderWatcher {
+    pub fn new() -> ShaderWatcher {
+        ShaderWatcher::default()
+    }
+
+    /// Watcher passing sources through `preprocessor` on reloads,
+    /// for shaders created with `Shader::new_preprocessed`.
+    pub fn with_preprocessor(preprocessor: Preprocessor) -> ShaderWatcher {
+        ShaderWatcher {
+            preprocessor: Some(preprocessor),
+            shaders: vec![],
+        }
+    }
+
+    pub fn watch<P: AsRef<Path>>(&mut self, shader: Shader, vertex_path: P, fragment_path: P) {
+        let vertex_path = vertex_path.as_ref().to_path_buf();
+        let fragment_path = fragment_path.as_ref().to_path_buf();
+        let modified = last_modified(&vertex_path, &fragment_path);
+
+        self.shaders.push(WatchedShader {
+            shader,
+            vertex_path,
+            fragment_path,
+            modified,
+        });
+    }
+
+    pub fn unwatch(&mut self, shader: Shader) {
+        self.shaders.retain(|watched| watched.shader != shader);
+    }
+
+    /// Reload shaders modified since the last poll, supposed to be called once per frame.
+    /// Returns the results of reloads that happened.
+    pub fn poll(&mut self, ctx: &mut Context) -> Vec<(Shader, Result<(), ShaderError>)> {
+        let mut reloaded = vec![];
+
+        for watched in &mut self.shaders {
+            let modified = last_modified(&watched.vertex_path, &watched.fragment_path);
+            if modified.is_none() || modified == watched.modified {
+                continue;
+            }
+
+            // file may be in the middle of being saved, it will be picked up on the next poll
+            let vertex = std::fs::read_to_string(&watched.vertex_path);
+            let fragment = std::fs::read_to_string(&watched.fragment_path);
+            let (vertex, fragment) = match (vertex, fragment) {
+                (Ok(vertex), Ok(fragment)) => (vertex, fragment),
+                _ => continue,
+            };
+            watched.modified = modified;
+
+            let shader = watched.shader;
+            let res = match &self.preprocessor {
+                Some(preprocessor) => preprocessor.with_sources(
+                    (&watched.vertex_path.to_string_lossy(), &vertex),
+                    (&watched.fragment_path.to_string_lossy(), &fragment),
+                    |vertex, fragment| shader.reload(ctx, vertex, fragment),
+                ),
+                None => shader.reload(ctx, &vertex, &fragment),
+            };
+            reloaded.push((shader, res));
+        }
+
+        reloaded
+    }
+}
+
+fn last_modified(vertex_path: &Path, fragment_path: &Path) -> Option<SystemTime> {
+    let modified = |path: &Path| std::fs::metadata(path).and_then(|m| m.modified()).ok();
+
+    Some(modified(vertex_path)?.max(modified(fragment_path)?))
+}
@@ -204,6 +204,9 @@ where
                     native::linux_x11::run(&conf, f);
                 }
             }
+            conf::LinuxBackend::Headless => {
+                native::linux_headless::run(&conf, f).expect("Headless backend failed")
+            }
         }
     }
 
@@ -61,6 +61,9 @@ pub mod linux_x11;
 #[cfg(target_os = "linux")]
 pub mod linux_wayland;
 
+#[cfg(target_os = "linux")]
+pub mod linux_headless;
+
 #[cfg(target_os = "android")]
 pub mod android;
 
@@ -96,4 +99,8 @@ pub use wasm::webgl as gl;
 #[cfg(any(target_os = "macos", target_os = "ios"))]
 pub use apple::gl;
 
+#[cfg(target_os = "ios")]
 pub mod query_stab;
+
+#[cfg(any(target_arch = "wasm32", target_os = "macos", target_os = "ios"))]
+pub mod debug_stab;
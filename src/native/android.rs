@@ -181,6 +181,7 @@ struct MainThreadState {
     window: *mut ndk_sys::ANativeWindow,
     event_handler: Box<dyn EventHandler>,
     quit: bool,
+    gl_debug: bool,
 }
 
 impl MainThreadState {
@@ -316,10 +317,47 @@ impl MainThreadState {
                 .draw(self.context.with_display(&mut self.display));
 
             unsafe {
-                (self.libegl.eglSwapBuffers.unwrap())(self.egl_display, self.surface);
+                if (self.libegl.eglSwapBuffers.unwrap())(self.egl_display, self.surface) == 0
+                    && (self.libegl.eglGetError.unwrap())() == egl::EGL_CONTEXT_LOST as _
+                {
+                    self.restore_context();
+                }
             }
         }
     }
+
+    /// Replace the context lost to a power management event with a new one
+    /// for the same surface.
+    unsafe fn restore_context(&mut self) {
+        self.event_handler
+            .context_lost_event(self.context.with_display(&mut self.display));
+
+        (self.libegl.eglMakeCurrent.unwrap())(
+            self.egl_display,
+            std::ptr::null_mut(),
+            std::ptr::null_mut(),
+            std::ptr::null_mut(),
+        );
+        (self.libegl.eglDestroyContext.unwrap())(self.egl_display, self.egl_context);
+        self.egl_context = egl::create_context(
+            &mut self.libegl,
+            self.egl_display,
+            self.egl_config,
+            self.gl_debug,
+        )
+        .expect("Cant create EGL context");
+        let res = (self.libegl.eglMakeCurrent.unwrap())(
+            self.egl_display,
+            self.surface,
+            self.surface,
+            self.egl_context,
+        );
+        assert!(res != 0);
+
+        self.context.restore(gl::is_gl2());
+        self.event_handler
+            .context_restored_event(self.context.with_display(&mut self.display));
+    }
 }
 
 /// Get the JNI Env by calling ndk's AttachCurrentThread
@@ -409,6 +447,7 @@ where
             &mut libegl,
             std::ptr::null_mut(), /* EGL_DEFAULT_DISPLAY */
             conf.platform.framebuffer_alpha,
+            conf.platform.gl_debug,
         )
         .expect("Cant create EGL context");
 
@@ -432,6 +471,8 @@ where
         }
 
         let mut context = GraphicsContext::new(gl::is_gl2());
+        context.set_program_cache(conf.program_cache_dir());
+        context.set_debug_output(conf.platform.gl_debug);
 
         let mut display = AndroidDisplay {
             screen_width,
@@ -450,6 +491,7 @@ where
             window,
             event_handler,
             quit: false,
+            gl_debug: conf.platform.gl_debug,
         };
 
         while !s.quit {
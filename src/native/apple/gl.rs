@@ -770,6 +770,7 @@ pub const GL_RENDERBUFFER_DEPTH_SIZE: u32 = 36180;
 pub const GL_RENDERBUFFER_STENCIL_SIZE: u32 = 36181;
 pub const GL_FRAMEBUFFER_INCOMPLETE_MULTISAMPLE: u32 = 36182;
 pub const GL_MAX_SAMPLES: u32 = 36183;
+pub const GL_MAX_VERTEX_ATTRIB_STRIDE: u32 = 33509;
 pub const GL_FRAMEBUFFER_SRGB: u32 = 36281;
 pub const GL_HALF_FLOAT: u32 = 5131;
 pub const GL_MAP_READ_BIT: u32 = 1;
@@ -790,6 +791,15 @@ pub const GL_RG8: u32 = 33323;
 pub const GL_RG16: u32 = 33324;
 pub const GL_R16F: u32 = 33325;
 pub const GL_R32F: u32 = 33326;
+pub const GL_COMPRESSED_RGBA_S3TC_DXT1_EXT: u32 = 33777;
+pub const GL_COMPRESSED_RGBA_S3TC_DXT3_EXT: u32 = 33778;
+pub const GL_COMPRESSED_RGBA_S3TC_DXT5_EXT: u32 = 33779;
+pub const GL_COMPRESSED_RGB8_ETC2: u32 = 37492;
+pub const GL_COMPRESSED_RGBA8_ETC2_EAC: u32 = 37496;
+pub const GL_COMPRESSED_RGBA_ASTC_4x4_KHR: u32 = 37808;
+pub const GL_COMPRESSED_RGBA_ASTC_8x8_KHR: u32 = 37815;
+pub const GL_TEXTURE_MAX_ANISOTROPY_EXT: u32 = 34046;
+pub const GL_MAX_TEXTURE_MAX_ANISOTROPY_EXT: u32 = 34047;
 pub const GL_RG16F: u32 = 33327;
 pub const GL_RG32F: u32 = 33328;
 pub const GL_R8I: u32 = 33329;
@@ -895,6 +905,34 @@ pub const GL_TEXTURE_SWIZZLE_B: u32 = 36420;
 pub const GL_TEXTURE_SWIZZLE_A: u32 = 36421;
 pub const GL_TEXTURE_SWIZZLE_RGBA: u32 = 36422;
 pub const GL_TIME_ELAPSED: u32 = 35007;
+pub const GL_GPU_DISJOINT_EXT: u32 = 36795;
+pub const GL_VERTICES_SUBMITTED: u32 = 33518;
+pub const GL_PRIMITIVES_SUBMITTED: u32 = 33519;
+pub const GL_VERTEX_SHADER_INVOCATIONS: u32 = 33520;
+pub const GL_FRAGMENT_SHADER_INVOCATIONS: u32 = 33524;
+pub const GL_CLIPPING_INPUT_PRIMITIVES: u32 = 33526;
+pub const GL_CLIPPING_OUTPUT_PRIMITIVES: u32 = 33527;
+pub const GL_DEBUG_OUTPUT: u32 = 37600;
+pub const GL_DEBUG_OUTPUT_SYNCHRONOUS: u32 = 33346;
+pub const GL_DEBUG_SOURCE_API: u32 = 33350;
+pub const GL_DEBUG_SOURCE_WINDOW_SYSTEM: u32 = 33351;
+pub const GL_DEBUG_SOURCE_SHADER_COMPILER: u32 = 33352;
+pub const GL_DEBUG_SOURCE_THIRD_PARTY: u32 = 33353;
+pub const GL_DEBUG_SOURCE_APPLICATION: u32 = 33354;
+pub const GL_DEBUG_TYPE_ERROR: u32 = 33356;
+pub const GL_DEBUG_TYPE_DEPRECATED_BEHAVIOR: u32 = 33357;
+pub const GL_DEBUG_TYPE_UNDEFINED_BEHAVIOR: u32 = 33358;
+pub const GL_DEBUG_TYPE_PORTABILITY: u32 = 33359;
+pub const GL_DEBUG_TYPE_PERFORMANCE: u32 = 33360;
+pub const GL_DEBUG_TYPE_MARKER: u32 = 33384;
+pub const GL_DEBUG_TYPE_PUSH_GROUP: u32 = 33385;
+pub const GL_DEBUG_TYPE_POP_GROUP: u32 = 33386;
+pub const GL_DEBUG_SEVERITY_HIGH: u32 = 37190;
+pub const GL_DEBUG_SEVERITY_MEDIUM: u32 = 37191;
+pub const GL_DEBUG_SEVERITY_LOW: u32 = 37192;
+pub const GL_DEBUG_SEVERITY_NOTIFICATION: u32 = 33387;
+pub const GL_BUFFER: u32 = 33504;
+pub const GL_PROGRAM: u32 = 33506;
 pub const GL_TIMESTAMP: u32 = 36392;
 pub const GL_INT_2_10_10_10_REV: u32 = 36255;
 pub const GL_DRAW_INDIRECT_BUFFER: u32 = 36671;
@@ -1389,6 +1427,9 @@ extern "C" {
 extern "C" {
     pub fn glGetString(name: GLenum) -> *const GLubyte;
 }
+extern "C" {
+    pub fn glGetStringi(name: GLenum, index: GLuint) -> *const GLubyte;
+}
 extern "C" {
     pub fn glGetTexImage(
         target: GLenum,
@@ -0,0 +1,58 @@
+//! KHR_debug functions missing from webgl and the apple bindings.
+//! `Features::debug_output` is always false there, so these are never called.
+
+#![allow(non_snake_case)]
+
+use crate::gl::{GLchar, GLenum, GLsizei, GLuint};
+
+pub type GLDEBUGPROC = Option<
+    extern "system" fn(
+        source: GLenum,
+        type_: GLenum,
+        id: GLuint,
+        severity: GLenum,
+        length: GLsizei,
+        message: *const GLchar,
+        userParam: *mut ::std::os::raw::c_void,
+    ),
+>;
+
+pub unsafe fn glDebugMessageCallback(
+    _callback: GLDEBUGPROC,
+    _userParam: *const ::std::os::raw::c_void,
+) {
+    unreachable!();
+}
+
+pub unsafe fn glDebugMessageInsert(
+    _source: GLenum,
+    _type_: GLenum,
+    _id: GLuint,
+    _severity: GLenum,
+    _length: GLsizei,
+    _buf: *const GLchar,
+) {
+    unreachable!();
+}
+
+pub unsafe fn glObjectLabel(
+    _identifier: GLenum,
+    _name: GLuint,
+    _length: GLsizei,
+    _label: *const GLchar,
+) {
+    unreachable!();
+}
+
+pub unsafe fn glPushDebugGroup(
+    _source: GLenum,
+    _id: GLuint,
+    _length: GLsizei,
+    _message: *const GLchar,
+) {
+    unreachable!();
+}
+
+pub unsafe fn glPopDebugGroup() {
+    unreachable!();
+}
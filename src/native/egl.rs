@@ -19,7 +19,9 @@ pub type EGLNativeWindowType = ::std::os::raw::c_ulong;
 pub use core::ptr::null_mut;
 
 pub const EGL_SUCCESS: u32 = 12288;
+pub const EGL_CONTEXT_LOST: u32 = 12302;
 
+pub const EGL_PBUFFER_BIT: u32 = 1;
 pub const EGL_WINDOW_BIT: u32 = 4;
 
 pub const EGL_ALPHA_SIZE: u32 = 12321;
@@ -34,6 +36,10 @@ pub const EGL_HEIGHT: u32 = 12374;
 pub const EGL_SURFACE_TYPE: u32 = 12339;
 pub const EGL_NONE: u32 = 12344;
 pub const EGL_CONTEXT_CLIENT_VERSION: u32 = 12440;
+pub const EGL_CONTEXT_FLAGS_KHR: u32 = 12540;
+pub const EGL_CONTEXT_OPENGL_DEBUG_BIT_KHR: u32 = 1;
+pub const EGL_EXTENSIONS: u32 = 12373;
+pub const EGL_PLATFORM_SURFACELESS_MESA: u32 = 0x31DD;
 
 pub type NativeDisplayType = EGLNativeDisplayType;
 pub type NativePixmapType = EGLNativePixmapType;
@@ -176,6 +182,13 @@ pub type PFNEGLSURFACEATTRIBPROC = ::std::option::Option<
 >;
 pub type PFNEGLSWAPINTERVALPROC =
     ::std::option::Option<unsafe extern "C" fn(dpy: EGLDisplay, interval: EGLint) -> EGLBoolean>;
+pub type PFNEGLGETPLATFORMDISPLAYEXTPROC = ::std::option::Option<
+    unsafe extern "C" fn(
+        platform: EGLint,
+        native_display: *mut ::std::os::raw::c_void,
+        attrib_list: *const EGLint,
+    ) -> EGLDisplay,
+>;
 
 pub struct LibEgl {
     pub module: crate::native::module::Module,
@@ -252,15 +265,20 @@ impl LibEgl {
 pub enum EglError {
     NoDisplay,
     InitializeFailed,
+    NoConfig,
     CreateContextFailed,
 }
 
 pub struct Egl {}
 
+/// # Safety
+///
+/// `display` is a native display of the window system EGL was loaded for.
 pub unsafe fn create_egl_context(
     egl: &mut LibEgl,
     display: *mut std::ffi::c_void,
     alpha: bool,
+    debug: bool,
 ) -> Result<(EGLContext, EGLConfig, EGLDisplay), EglError> {
     let display = (egl.eglGetDisplay.unwrap())(display as _);
     if display == /* EGL_NO_DISPLAY */ null_mut() {
@@ -271,10 +289,71 @@ pub unsafe fn create_egl_context(
         return Err(EglError::InitializeFailed);
     }
 
+    create_egl_context_on_display(egl, display, alpha, EGL_WINDOW_BIT, debug)
+}
+
+/// Get an EGL display that does not need any window system at all.
+/// Uses EGL_MESA_platform_surfaceless when the client library supports it,
+/// otherwise falls back to EGL_DEFAULT_DISPLAY.
+///
+/// # Safety
+///
+/// `egl` holds the entry points of a loaded libEGL.
+pub unsafe fn get_headless_display(egl: &mut LibEgl) -> Result<EGLDisplay, EglError> {
+    let client_extensions =
+        (egl.eglQueryString.unwrap())(/* EGL_NO_DISPLAY */ null_mut(), EGL_EXTENSIONS as _);
+    let has_surfaceless = !client_extensions.is_null()
+        && std::ffi::CStr::from_ptr(client_extensions)
+            .to_string_lossy()
+            .split(' ')
+            .any(|ext| ext == "EGL_MESA_platform_surfaceless");
+
+    let mut display = null_mut();
+    if has_surfaceless {
+        let name = std::ffi::CString::new("eglGetPlatformDisplayEXT").unwrap();
+        let get_platform_display: PFNEGLGETPLATFORMDISPLAYEXTPROC =
+            std::mem::transmute((egl.eglGetProcAddress.unwrap())(name.as_ptr() as _));
+        if let Some(get_platform_display) = get_platform_display {
+            display = get_platform_display(
+                EGL_PLATFORM_SURFACELESS_MESA as _,
+                /* EGL_DEFAULT_DISPLAY */ null_mut(),
+                std::ptr::null(),
+            );
+        }
+    }
+    if display.is_null() {
+        display = (egl.eglGetDisplay.unwrap())(/* EGL_DEFAULT_DISPLAY */ null_mut());
+    }
+    // EGL_NO_DISPLAY
+    if display.is_null() {
+        return Err(EglError::NoDisplay);
+    }
+
+    if (egl.eglInitialize.unwrap())(display, null_mut(), null_mut()) == 0 {
+        return Err(EglError::InitializeFailed);
+    }
+
+    Ok(display)
+}
+
+/// Choose a config with the given EGL_SURFACE_TYPE bits on an already initialized
+/// display and create a GLES context with it.
+/// A `debug` context is only a hint, without EGL_KHR_create_context a regular one is created.
+///
+/// # Safety
+///
+/// `display` is an EGL display initialized with `egl`.
+pub unsafe fn create_egl_context_on_display(
+    egl: &mut LibEgl,
+    display: EGLDisplay,
+    alpha: bool,
+    surface_type: u32,
+    debug: bool,
+) -> Result<(EGLContext, EGLConfig, EGLDisplay), EglError> {
     let alpha_size = if alpha { 8 } else { 0 };
     #[rustfmt::skip]
     let cfg_attributes = vec![
-        EGL_SURFACE_TYPE, EGL_WINDOW_BIT,
+        EGL_SURFACE_TYPE, surface_type,
         EGL_RED_SIZE, 8,
         EGL_GREEN_SIZE, 8,
         EGL_BLUE_SIZE, 8,
@@ -293,7 +372,9 @@ pub unsafe fn create_egl_context(
         32,
         &mut cfg_count as *mut _ as *mut _,
     );
-    assert!(cfg_count > 0);
+    if cfg_count == 0 {
+        return Err(EglError::NoConfig);
+    }
     assert!(cfg_count <= 32);
 
     // find config with 8-bit rgb buffer if available, ndk sample does not trust egl spec
@@ -324,16 +405,50 @@ pub unsafe fn create_egl_context(
     if !exact_cfg_found {
         config = available_cfgs[0];
     }
-    let ctx_attributes = vec![EGL_CONTEXT_CLIENT_VERSION, 2, EGL_NONE];
-    let context = (egl.eglCreateContext.unwrap())(
-        display,
-        config,
-        /* EGL_NO_CONTEXT */ null_mut(),
-        ctx_attributes.as_ptr() as _,
-    );
+    let context = create_context(egl, display, config, debug)?;
+
+    return Ok((context, config, display));
+}
+
+/// GLES context for a config chosen by `create_egl_context_on_display`,
+/// also used to replace a lost context.
+///
+/// # Safety
+///
+/// `config` is a config of `display`, an EGL display initialized with `egl`.
+pub unsafe fn create_context(
+    egl: &mut LibEgl,
+    display: EGLDisplay,
+    config: EGLConfig,
+    debug: bool,
+) -> Result<EGLContext, EglError> {
+    let mut context = null_mut();
+    if debug {
+        #[rustfmt::skip]
+        let ctx_attributes = [
+            EGL_CONTEXT_CLIENT_VERSION, 2,
+            EGL_CONTEXT_FLAGS_KHR, EGL_CONTEXT_OPENGL_DEBUG_BIT_KHR,
+            EGL_NONE,
+        ];
+        context = (egl.eglCreateContext.unwrap())(
+            display,
+            config,
+            /* EGL_NO_CONTEXT */ null_mut(),
+            ctx_attributes.as_ptr() as _,
+        );
+    }
+    if context.is_null() {
+        let ctx_attributes = [EGL_CONTEXT_CLIENT_VERSION, 2, EGL_NONE];
+        context = (egl.eglCreateContext.unwrap())(
+            display,
+            config,
+            /* EGL_NO_CONTEXT */ null_mut(),
+            ctx_attributes.as_ptr() as _,
+        );
+    }
     if context.is_null() {
         return Err(EglError::CreateContextFailed);
     }
 
-    return Ok((context, config, display));
+    Ok(context)
 }
@@ -62,6 +62,7 @@ struct WindowPayload {
     context: Option<GraphicsContext>,
     event_handler: Option<Box<dyn EventHandler>>,
     gles2: bool,
+    program_cache_dir: Option<std::path::PathBuf>,
     f: Option<Box<dyn 'static + FnOnce(&mut crate::Context) -> Box<dyn EventHandler>>>,
 }
 impl WindowPayload {
@@ -153,7 +154,9 @@ pub fn define_glk_view_dlg() -> *const Class {
         let payload = get_window_payload(this);
         if payload.event_handler.is_none() {
             let f = payload.f.take().unwrap();
-            payload.context = Some(GraphicsContext::new(payload.gles2));
+            let mut context = GraphicsContext::new(payload.gles2);
+            context.set_program_cache(payload.program_cache_dir.take());
+            payload.context = Some(context);
             payload.event_handler = Some(f(payload
                 .context
                 .as_mut()
@@ -253,6 +256,7 @@ pub fn define_app_delegate() -> *const Class {
                 event_handler: None,
                 context: None,
                 gles2,
+                program_cache_dir: conf.program_cache_dir(),
             });
             let payload_ptr = Box::into_raw(payload) as *mut std::ffi::c_void;
 
@@ -0,0 +1,143 @@
+//! Windowless backend: GL context is created through EGL on a pbuffer surface,
+//! with no X11 or Wayland server involved.
+//! Designed for CI and render tests, with Mesa's llvmpipe in mind.
+
+use crate::{
+    event::EventHandler,
+    native::{egl, gl, NativeDisplayData},
+    Context, GraphicsContext,
+};
+
+pub(crate) struct HeadlessDisplay {
+    clipboard: Option<String>,
+    data: NativeDisplayData,
+}
+
+impl crate::native::NativeDisplay for HeadlessDisplay {
+    fn screen_size(&self) -> (f32, f32) {
+        (self.data.screen_width as _, self.data.screen_height as _)
+    }
+    fn dpi_scale(&self) -> f32 {
+        self.data.dpi_scale
+    }
+    fn high_dpi(&self) -> bool {
+        self.data.high_dpi
+    }
+    fn order_quit(&mut self) {
+        self.data.quit_ordered = true;
+    }
+    fn request_quit(&mut self) {
+        self.data.quit_requested = true;
+    }
+    fn cancel_quit(&mut self) {
+        self.data.quit_requested = false;
+    }
+
+    fn set_cursor_grab(&mut self, _grab: bool) {}
+    fn show_mouse(&mut self, _shown: bool) {}
+    fn set_mouse_cursor(&mut self, _cursor_icon: crate::CursorIcon) {}
+    // the surface size is fixed at creation
+    fn set_window_size(&mut self, _new_width: u32, _new_height: u32) {}
+    fn set_fullscreen(&mut self, _fullscreen: bool) {}
+    fn clipboard_get(&mut self) -> Option<String> {
+        self.clipboard.clone()
+    }
+    fn clipboard_set(&mut self, data: &str) {
+        self.clipboard = Some(data.to_owned());
+    }
+    fn as_any(&mut self) -> &mut dyn std::any::Any {
+        self
+    }
+}
+
+pub fn run<F>(conf: &crate::conf::Conf, f: &mut Option<F>) -> Option<()>
+where
+    F: 'static + FnOnce(&mut Context) -> Box<dyn EventHandler>,
+{
+    unsafe {
+        let mut libegl = egl::LibEgl::try_load()?;
+        let egl_display = egl::get_headless_display(&mut libegl).ok()?;
+        let (egl_context, config, egl_display) = egl::create_egl_context_on_display(
+            &mut libegl,
+            egl_display,
+            conf.platform.framebuffer_alpha,
+            egl::EGL_PBUFFER_BIT,
+            conf.platform.gl_debug,
+        )
+        .ok()?;
+
+        #[rustfmt::skip]
+        let surface_attributes = [
+            egl::EGL_WIDTH, conf.window_width as u32,
+            egl::EGL_HEIGHT, conf.window_height as u32,
+            egl::EGL_NONE,
+        ];
+        let egl_surface = (libegl.eglCreatePbufferSurface.unwrap())(
+            egl_display,
+            config,
+            surface_attributes.as_ptr() as _,
+        );
+
+        if egl_surface.is_null() {
+            (libegl.eglDestroyContext.unwrap())(egl_display, egl_context);
+            return None;
+        }
+        if (libegl.eglMakeCurrent.unwrap())(egl_display, egl_surface, egl_surface, egl_context) == 0
+        {
+            (libegl.eglDestroySurface.unwrap())(egl_display, egl_surface);
+            (libegl.eglDestroyContext.unwrap())(egl_display, egl_context);
+            return None;
+        }
+
+        gl::load_gl_funcs(|proc| {
+            let name = std::ffi::CString::new(proc).unwrap();
+            libegl.eglGetProcAddress.expect("non-null function pointer")(name.as_ptr() as _)
+        });
+
+        let mut display = HeadlessDisplay {
+            clipboard: None,
+            data: NativeDisplayData {
+                screen_width: conf.window_width,
+                screen_height: conf.window_height,
+                ..Default::default()
+            },
+        };
+
+        let mut context = GraphicsContext::new(gl::is_gl2());
+        context.set_program_cache(conf.program_cache_dir());
+        context.set_debug_output(conf.platform.gl_debug);
+
+        let mut data = (f.take().unwrap())(context.with_display(&mut display));
+
+        let mut frame = 0;
+        while !display.data.quit_ordered && conf.platform.headless_frame_count != Some(frame) {
+            data.update(context.with_display(&mut display));
+            data.draw(context.with_display(&mut display));
+
+            (libegl.eglSwapBuffers.unwrap())(egl_display, egl_surface);
+
+            if display.data.quit_requested {
+                data.quit_requested_event(context.with_display(&mut display));
+                if display.data.quit_requested {
+                    display.data.quit_ordered = true;
+                }
+            }
+            frame += 1;
+        }
+
+        // the event handler may own GL resources, drop it while the context is still alive
+        drop(data);
+
+        (libegl.eglMakeCurrent.unwrap())(
+            egl_display,
+            std::ptr::null_mut(),
+            std::ptr::null_mut(),
+            std::ptr::null_mut(),
+        );
+        (libegl.eglDestroySurface.unwrap())(egl_display, egl_surface);
+        (libegl.eglDestroyContext.unwrap())(egl_display, egl_context);
+        (libegl.eglTerminate.unwrap())(egl_display);
+    }
+
+    Some(())
+}
@@ -379,6 +379,7 @@ where
             &mut libegl,
             wdisplay as *mut _,
             conf.platform.framebuffer_alpha,
+            conf.platform.gl_debug,
         )
         .unwrap();
 
@@ -484,7 +485,10 @@ where
             ));
         }
 
-        payload.context = Some(crate::GraphicsContext::new(false));
+        let mut context = crate::GraphicsContext::new(false);
+        context.set_program_cache(conf.program_cache_dir());
+        context.set_debug_output(conf.platform.gl_debug);
+        payload.context = Some(context);
         payload.display.data.screen_width = conf.window_width;
         payload.display.data.screen_height = conf.window_height;
 
@@ -697,7 +697,8 @@ where
     let depth = glx.depth;
     let window = display.create_window(visual, depth, conf);
     display.window = window;
-    let (glx_context, glx_window) = glx.create_context(&mut display, window);
+    let (glx_context, glx_window) =
+        glx.create_context(&mut display, window, conf.platform.gl_debug);
     glx.swap_interval(
         &mut display,
         glx_window,
@@ -720,6 +721,8 @@ where
     display.data.screen_height = h;
 
     let mut context = GraphicsContext::new(gl::is_gl2());
+    context.set_program_cache(conf.program_cache_dir());
+    context.set_debug_output(conf.platform.gl_debug);
 
     let mut data = (f.take().unwrap())(context.with_display(&mut display));
 
@@ -773,6 +776,7 @@ where
         &mut egl_lib,
         display.display as *mut _,
         conf.platform.framebuffer_alpha,
+        conf.platform.gl_debug,
     )
     .unwrap();
 
@@ -806,6 +810,8 @@ where
     (display.libx11.XFlush)(display.display);
 
     let mut context = GraphicsContext::new(gl::is_gl2());
+    context.set_program_cache(conf.program_cache_dir());
+    context.set_debug_output(conf.platform.gl_debug);
 
     let (w, h) = display.query_window_size(window);
     display.data.screen_width = w;
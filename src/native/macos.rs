@@ -192,6 +192,7 @@ struct WindowPayload {
     event_handler: Option<Box<dyn EventHandler>>,
     f: Option<Box<dyn 'static + FnOnce(&mut crate::Context) -> Box<dyn EventHandler>>>,
     modifiers: Modifiers,
+    program_cache_dir: Option<std::path::PathBuf>,
 }
 impl WindowPayload {
     pub fn context(&mut self) -> Option<(&mut Context, &mut dyn EventHandler)> {
@@ -365,7 +366,9 @@ pub fn define_cocoa_view_class() -> *const Class {
             let () = msg_send![ctx, makeCurrentContext];
         }
 
-        payload.context = Some(GraphicsContext::new(false));
+        let mut context = GraphicsContext::new(false);
+        context.set_program_cache(payload.program_cache_dir.take());
+        payload.context = Some(context);
 
         let f = payload.f.take().unwrap();
         payload.event_handler = Some(f(payload
@@ -668,6 +671,7 @@ where
         event_handler: None,
         context: None,
         modifiers: Modifiers::default(),
+        program_cache_dir: conf.program_cache_dir(),
     };
 
     let app_delegate_class = define_app_delegate();
@@ -84,6 +84,8 @@ struct WasmGlobals {
     event_handler: Box<dyn EventHandler>,
     context: GraphicsContext,
     display: WasmDisplay,
+    /// Between "webglcontextlost" and "webglcontextrestored".
+    context_lost: bool,
 }
 
 thread_local! {
@@ -145,6 +147,7 @@ where
             event_handler: f(context.with_display(&mut display)),
             context,
             display,
+            context_lost: false,
         });
     });
 
@@ -187,6 +190,9 @@ extern "C" {
 
     pub fn sapp_is_elapsed_timer_supported() -> bool;
 
+    /// Check and enable WebGL extension.
+    pub fn webgl_extension_supported(name: *const u8, len: usize) -> bool;
+
     pub fn sapp_set_fullscreen(fullscreen: bool);
     pub fn sapp_is_fullscreen() -> bool;
     pub fn sapp_set_window_size(new_width: u32, new_height: u32);
@@ -272,9 +278,32 @@ pub extern "C" fn frame() {
         globals
             .event_handler
             .update(globals.context.with_display(&mut globals.display));
+        if !globals.context_lost {
+            globals
+                .event_handler
+                .draw(globals.context.with_display(&mut globals.display));
+        }
+    });
+}
+
+#[no_mangle]
+pub extern "C" fn context_lost() {
+    with(|globals| {
+        globals.context_lost = true;
+        globals
+            .event_handler
+            .context_lost_event(globals.context.with_display(&mut globals.display));
+    });
+}
+
+#[no_mangle]
+pub extern "C" fn context_restored() {
+    with(|globals| {
+        globals.context_lost = false;
+        globals.context.restore(false);
         globals
             .event_handler
-            .draw(globals.context.with_display(&mut globals.display));
+            .context_restored_event(globals.context.with_display(&mut globals.display));
     });
 }
 
@@ -31,6 +31,11 @@ pub type GLclampd = f64;
 
 pub const GL_INT_2_10_10_10_REV: u32 = 0x8D9F;
 pub const GL_PROGRAM_POINT_SIZE: u32 = 0x8642;
+pub const GL_DEPTH_CLAMP: u32 = 0x864F;
+pub const GL_POINT: u32 = 0x1B00;
+pub const GL_LINE: u32 = 0x1B01;
+pub const GL_FILL: u32 = 0x1B02;
+pub const GL_ALIASED_LINE_WIDTH_RANGE: u32 = 0x846E;
 pub const GL_STENCIL_ATTACHMENT: u32 = 0x8D20;
 pub const GL_DEPTH_ATTACHMENT: u32 = 0x8D00;
 pub const GL_COLOR_ATTACHMENT2: u32 = 0x8CE2;
@@ -38,6 +43,8 @@ pub const GL_COLOR_ATTACHMENT0: u32 = 0x8CE0;
 pub const GL_COLOR_ATTACHMENT22: u32 = 0x8CF6;
 pub const GL_DRAW_FRAMEBUFFER: u32 = 0x8CA9;
 pub const GL_FRAMEBUFFER_COMPLETE: u32 = 0x8CD5;
+pub const GL_MAX_SAMPLES: u32 = 0x8D57;
+pub const GL_MAX_VERTEX_ATTRIB_STRIDE: u32 = 0x82E5;
 pub const GL_NUM_EXTENSIONS: u32 = 0x821D;
 pub const GL_INFO_LOG_LENGTH: u32 = 0x8B84;
 pub const GL_VERTEX_SHADER: u32 = 0x8B31;
@@ -206,6 +213,70 @@ pub const GL_RG8: u32 = 0x822B;
 pub const GL_RG16: u32 = 0x822C;
 pub const GL_R16F: u32 = 0x822D;
 pub const GL_R32F: u32 = 0x822E;
+pub const GL_SRGB8_ALPHA8: u32 = 0x8C43;
+pub const GL_DEPTH24_STENCIL8: u32 = 0x88F0;
+pub const GL_DEPTH_STENCIL: u32 = 0x84F9;
+pub const GL_UNSIGNED_INT_24_8: u32 = 0x84FA;
+pub const GL_HALF_FLOAT: u32 = 0x140B;
+pub const GL_DEPTH_STENCIL_ATTACHMENT: u32 = 0x821A;
+pub const GL_COMPRESSED_RGBA_S3TC_DXT1_EXT: u32 = 0x83F1;
+pub const GL_COMPRESSED_RGBA_S3TC_DXT3_EXT: u32 = 0x83F2;
+pub const GL_COMPRESSED_RGBA_S3TC_DXT5_EXT: u32 = 0x83F3;
+pub const GL_COMPRESSED_RGB8_ETC2: u32 = 0x9274;
+pub const GL_COMPRESSED_RGBA8_ETC2_EAC: u32 = 0x9278;
+pub const GL_COMPRESSED_RGBA_ASTC_4x4_KHR: u32 = 0x93B0;
+pub const GL_COMPRESSED_RGBA_ASTC_8x8_KHR: u32 = 0x93B7;
+pub const GL_TEXTURE_COMPARE_MODE: u32 = 0x884C;
+pub const GL_TEXTURE_COMPARE_FUNC: u32 = 0x884D;
+pub const GL_COMPARE_REF_TO_TEXTURE: u32 = 0x884E;
+pub const GL_TEXTURE_MAX_ANISOTROPY_EXT: u32 = 0x84FE;
+pub const GL_MAX_TEXTURE_MAX_ANISOTROPY_EXT: u32 = 0x84FF;
+pub const GL_UNIFORM_BUFFER: u32 = 0x8A11;
+pub const GL_UNIFORM_BUFFER_OFFSET_ALIGNMENT: u32 = 0x8A34;
+pub const GL_UNIFORM_BLOCK_DATA_SIZE: u32 = 0x8A40;
+pub const GL_INVALID_INDEX: u32 = 0xFFFFFFFF;
+pub const GL_ACTIVE_UNIFORMS: u32 = 0x8B86;
+pub const GL_ACTIVE_UNIFORM_MAX_LENGTH: u32 = 0x8B87;
+pub const GL_ACTIVE_ATTRIBUTES: u32 = 0x8B89;
+pub const GL_ACTIVE_ATTRIBUTE_MAX_LENGTH: u32 = 0x8B8A;
+pub const GL_PROGRAM_BINARY_RETRIEVABLE_HINT: u32 = 0x8257;
+pub const GL_PROGRAM_BINARY_LENGTH: u32 = 0x8741;
+pub const GL_NUM_PROGRAM_BINARY_FORMATS: u32 = 0x87FE;
+pub const GL_DRAW_INDIRECT_BUFFER: u32 = 0x8F3F;
+pub const GL_MAP_WRITE_BIT: u32 = 0x0002;
+pub const GL_MAP_INVALIDATE_RANGE_BIT: u32 = 0x0004;
+pub const GL_MAP_UNSYNCHRONIZED_BIT: u32 = 0x0020;
+pub const GL_MAP_PERSISTENT_BIT: u32 = 0x0040;
+pub const GL_MAP_COHERENT_BIT: u32 = 0x0080;
+pub const GL_SYNC_GPU_COMMANDS_COMPLETE: u32 = 0x9117;
+pub const GL_SYNC_FLUSH_COMMANDS_BIT: u32 = 0x00000001;
+pub const GL_ALREADY_SIGNALED: u32 = 0x911A;
+pub const GL_CONDITION_SATISFIED: u32 = 0x911C;
+pub const GL_WAIT_FAILED: u32 = 0x911D;
+pub const GL_COPY_READ_BUFFER: u32 = 0x8F36;
+pub const GL_COPY_WRITE_BUFFER: u32 = 0x8F37;
+pub const GL_STREAM_COPY: u32 = 0x88E2;
+pub const GL_FLOAT_VEC2: u32 = 0x8B50;
+pub const GL_FLOAT_VEC3: u32 = 0x8B51;
+pub const GL_FLOAT_VEC4: u32 = 0x8B52;
+pub const GL_INT_VEC2: u32 = 0x8B53;
+pub const GL_INT_VEC3: u32 = 0x8B54;
+pub const GL_INT_VEC4: u32 = 0x8B55;
+pub const GL_BOOL: u32 = 0x8B56;
+pub const GL_UNSIGNED_INT_VEC2: u32 = 0x8DC6;
+pub const GL_UNSIGNED_INT_VEC3: u32 = 0x8DC7;
+pub const GL_UNSIGNED_INT_VEC4: u32 = 0x8DC8;
+pub const GL_BOOL_VEC2: u32 = 0x8B57;
+pub const GL_BOOL_VEC3: u32 = 0x8B58;
+pub const GL_BOOL_VEC4: u32 = 0x8B59;
+pub const GL_FLOAT_MAT4: u32 = 0x8B5C;
+pub const GL_SAMPLER_2D: u32 = 0x8B5E;
+pub const GL_SAMPLER_3D: u32 = 0x8B5F;
+pub const GL_SAMPLER_CUBE: u32 = 0x8B60;
+pub const GL_SAMPLER_2D_SHADOW: u32 = 0x8B62;
+pub const GL_SAMPLER_2D_ARRAY: u32 = 0x8DC1;
+pub const GL_SAMPLER_2D_ARRAY_SHADOW: u32 = 0x8DC4;
+pub const GL_SAMPLER_CUBE_SHADOW: u32 = 0x8DC5;
 pub const GL_RG16F: u32 = 0x822F;
 pub const GL_RG32F: u32 = 0x8230;
 pub const GL_R8I: u32 = 0x8231;
@@ -231,6 +302,9 @@ pub const GL_RGB16_SNORM: u32 = 0x8F9A;
 pub const GL_RGBA16_SNORM: u32 = 0x8F9B;
 pub const GL_RGBA16: u32 = 0x805B;
 pub const GL_MAX_TEXTURE_SIZE: u32 = 0x0D33;
+pub const GL_MAX_TEXTURE_IMAGE_UNITS: u32 = 0x8872;
+pub const GL_MAX_COLOR_ATTACHMENTS: u32 = 0x8CDF;
+pub const GL_MAX_DRAW_BUFFERS: u32 = 0x8824;
 pub const GL_MAX_CUBE_MAP_TEXTURE_SIZE: u32 = 0x851C;
 pub const GL_MAX_3D_TEXTURE_SIZE: u32 = 0x8073;
 pub const GL_MAX_ARRAY_TEXTURE_LAYERS: u32 = 0x88FF;
@@ -238,6 +312,8 @@ pub const GL_MAX_VERTEX_ATTRIBS: u32 = 0x8869;
 pub const GL_CLAMP_TO_BORDER: u32 = 0x812D;
 pub const GL_TEXTURE_BORDER_COLOR: u32 = 0x1004;
 pub const GL_UNPACK_ALIGNMENT: u32 = 3317;
+pub const GL_PACK_ALIGNMENT: u32 = 3333;
+pub const GL_CURRENT_PROGRAM: u32 = 0x8B8D;
 pub const GL_TEXTURE_SWIZZLE_R: u32 = 36418;
 pub const GL_TEXTURE_SWIZZLE_G: u32 = 36419;
 pub const GL_TEXTURE_SWIZZLE_B: u32 = 36420;
@@ -245,9 +321,42 @@ pub const GL_TEXTURE_SWIZZLE_A: u32 = 36421;
 pub const GL_TEXTURE_SWIZZLE_RGBA: u32 = 36422;
 pub const GL_DRAW_FRAMEBUFFER_BINDING: u32 = 36006;
 pub const GL_TIME_ELAPSED: u32 = 35007;
+pub const GL_QUERY_COUNTER_BITS: u32 = 0x8864;
+pub const GL_ANY_SAMPLES_PASSED: u32 = 0x8C2F;
+pub const GL_TIMESTAMP: u32 = 0x8E28;
+pub const GL_GPU_DISJOINT_EXT: u32 = 0x8FBB;
+pub const GL_VERTICES_SUBMITTED: u32 = 0x82EE;
+pub const GL_PRIMITIVES_SUBMITTED: u32 = 0x82EF;
+pub const GL_VERTEX_SHADER_INVOCATIONS: u32 = 0x82F0;
+pub const GL_FRAGMENT_SHADER_INVOCATIONS: u32 = 0x82F4;
+pub const GL_CLIPPING_INPUT_PRIMITIVES: u32 = 0x82F6;
+pub const GL_CLIPPING_OUTPUT_PRIMITIVES: u32 = 0x82F7;
+pub const GL_DEBUG_OUTPUT: u32 = 0x92E0;
+pub const GL_DEBUG_OUTPUT_SYNCHRONOUS: u32 = 0x8242;
+pub const GL_DEBUG_SOURCE_API: u32 = 0x8246;
+pub const GL_DEBUG_SOURCE_WINDOW_SYSTEM: u32 = 0x8247;
+pub const GL_DEBUG_SOURCE_SHADER_COMPILER: u32 = 0x8248;
+pub const GL_DEBUG_SOURCE_THIRD_PARTY: u32 = 0x8249;
+pub const GL_DEBUG_SOURCE_APPLICATION: u32 = 0x824A;
+pub const GL_DEBUG_TYPE_ERROR: u32 = 0x824C;
+pub const GL_DEBUG_TYPE_DEPRECATED_BEHAVIOR: u32 = 0x824D;
+pub const GL_DEBUG_TYPE_UNDEFINED_BEHAVIOR: u32 = 0x824E;
+pub const GL_DEBUG_TYPE_PORTABILITY: u32 = 0x824F;
+pub const GL_DEBUG_TYPE_PERFORMANCE: u32 = 0x8250;
+pub const GL_DEBUG_TYPE_MARKER: u32 = 0x8268;
+pub const GL_DEBUG_TYPE_PUSH_GROUP: u32 = 0x8269;
+pub const GL_DEBUG_TYPE_POP_GROUP: u32 = 0x826A;
+pub const GL_DEBUG_SEVERITY_HIGH: u32 = 0x9146;
+pub const GL_DEBUG_SEVERITY_MEDIUM: u32 = 0x9147;
+pub const GL_DEBUG_SEVERITY_LOW: u32 = 0x9148;
+pub const GL_DEBUG_SEVERITY_NOTIFICATION: u32 = 0x826B;
+pub const GL_BUFFER: u32 = 0x82E0;
+pub const GL_PROGRAM: u32 = 0x82E2;
+pub const GL_TEXTURE: u32 = 0x1702;
 pub const GL_QUERY_RESULT: u32 = 34918;
 pub const GL_QUERY_RESULT_AVAILABLE: u32 = 34919;
 pub const GL_VENDOR: u32 = 0x1F00;
+pub const GL_RENDERER: u32 = 0x1F01;
 pub const GL_VERSION: u32 = 0x1F02;
 
 pub const WGL_NUMBER_PIXEL_FORMATS_ARB: u32 = 0x2000;
@@ -1022,6 +1131,9 @@ extern "C" {
 extern "C" {
     pub fn glGetQueryObjectiv(id: GLuint, pname: GLenum, params: *mut GLint);
 }
+extern "C" {
+    pub fn glGetQueryObjectuiv(id: GLuint, pname: GLenum, params: *mut GLuint);
+}
 extern "C" {
     pub fn glGetQueryObjectui64v(id: GLuint, pname: GLenum, params: *mut GLuint64);
 }
@@ -1311,6 +1423,68 @@ extern "C" {
         instancecount: GLsizei,
     );
 }
+extern "C" {
+    pub fn glDrawElementsBaseVertex(
+        mode: GLenum,
+        count: GLsizei,
+        type_: GLenum,
+        indices: *const ::std::os::raw::c_void,
+        basevertex: GLint,
+    );
+}
+extern "C" {
+    pub fn glDrawElementsInstancedBaseVertex(
+        mode: GLenum,
+        count: GLsizei,
+        type_: GLenum,
+        indices: *const ::std::os::raw::c_void,
+        instancecount: GLsizei,
+        basevertex: GLint,
+    );
+}
+extern "C" {
+    pub fn glMultiDrawElements(
+        mode: GLenum,
+        count: *const GLsizei,
+        type_: GLenum,
+        indices: *const *const ::std::os::raw::c_void,
+        drawcount: GLsizei,
+    );
+}
+extern "C" {
+    pub fn glMultiDrawElementsBaseVertex(
+        mode: GLenum,
+        count: *const GLsizei,
+        type_: GLenum,
+        indices: *const *const ::std::os::raw::c_void,
+        drawcount: GLsizei,
+        basevertex: *const GLint,
+    );
+}
+extern "C" {
+    pub fn glDrawElementsIndirect(
+        mode: GLenum,
+        type_: GLenum,
+        indirect: *const ::std::os::raw::c_void,
+    );
+}
+extern "C" {
+    pub fn glMultiDrawElementsIndirect(
+        mode: GLenum,
+        type_: GLenum,
+        indirect: *const ::std::os::raw::c_void,
+        drawcount: GLsizei,
+        stride: GLsizei,
+    );
+}
+extern "C" {
+    pub fn glBufferStorage(
+        target: GLenum,
+        size: GLsizeiptr,
+        data: *const ::std::os::raw::c_void,
+        flags: GLbitfield,
+    );
+}
 extern "C" {
     pub fn glFenceSync(condition: GLenum, flags: GLbitfield) -> GLsync;
 }
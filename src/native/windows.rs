@@ -844,11 +844,14 @@ where
             &mut display,
             conf.sample_count,
             conf.platform.swap_interval.unwrap_or(1),
+            conf.platform.gl_debug,
         );
 
         super::gl::load_gl_funcs(|proc| display.get_proc_address(proc));
 
         let mut context = GraphicsContext::new(crate::gl::is_gl2());
+        context.set_program_cache(conf.program_cache_dir());
+        context.set_debug_output(conf.platform.gl_debug);
 
         let event_handler = f(context.with_display(&mut display));
 
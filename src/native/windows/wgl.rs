@@ -370,6 +370,7 @@ impl Wgl {
         display: &mut Display,
         sample_count: i32,
         swap_interval: i32,
+        debug: bool,
     ) -> HGLRC {
         let pixel_format = self.wgl_find_pixel_format(display, sample_count);
         if 0 == pixel_format {
@@ -399,6 +400,7 @@ impl Wgl {
         // the highest version version possible
         // but, somehow, sometimes, it creates 2.1 context when 3.2 is in fact available
         // so this is a workaround: try to create 3.2, and if it fails, go for 2.1
+        let debug_bit = if debug { WGL_CONTEXT_DEBUG_BIT_ARB } else { 0 };
         let attrs = [
             WGL_CONTEXT_MAJOR_VERSION_ARB,
             3,
@@ -407,7 +409,7 @@ impl Wgl {
             3,
             1,
             WGL_CONTEXT_FLAGS_ARB,
-            WGL_CONTEXT_FORWARD_COMPATIBLE_BIT_ARB,
+            WGL_CONTEXT_FORWARD_COMPATIBLE_BIT_ARB | debug_bit,
             WGL_CONTEXT_PROFILE_MASK_ARB,
             WGL_CONTEXT_CORE_PROFILE_BIT_ARB,
         ];
@@ -426,7 +428,7 @@ impl Wgl {
                 WGL_CONTEXT_MINOR_VERSION_ARB,
                 1,
                 WGL_CONTEXT_FLAGS_ARB,
-                WGL_CONTEXT_CORE_PROFILE_BIT_ARB,
+                debug_bit,
                 0,
                 0,
             ];